@@ -1,8 +1,12 @@
 use std::cmp;
-use std::collections::VecDeque;
 use AttackResult::*;
 use EffectClass::*;
 
+#[path = "../2019/search.rs"]
+mod search;
+
+use search::Problem;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct Character {
     hp: i32,
@@ -120,49 +124,39 @@ fn cast_spell(state: &State, spell: Spell, hard: bool) -> AttackResult {
     Continue(result)
 }
 
-fn cheapest_strategy(spells: &Vec<Spell>, start: &State, hard: bool) -> Option<Vec<Spell>> {
-    let mut queue: VecDeque<(Vec<Spell>, State)> = VecDeque::new();
-    let mut min = None;
-
-    queue.push_back((vec![], start.clone()));
-
-    while queue.len() > 0 {
-        let (substrategy, state) = queue.pop_front().unwrap();
-        let cost = substrategy.iter().map(|spell| spell.cost).sum::<i32>();
-
-        for &spell in spells {
-            let attack_result = cast_spell(&state, spell, hard);
-
-            if let Lose = attack_result {
-                continue;
-            }
-
-            if let Some((min_cost, _)) = min {
-                if cost + spell.cost >= min_cost {
-                    continue;
-                }
-            }
-
-            let mut strategy = substrategy.clone();
-            strategy.push(spell);
+/// Spells cast one after another until the boss is defeated, costing their
+/// mana.
+struct Battle<'a> {
+    spells: &'a [Spell],
+    hard: bool
+}
 
-            if let Continue(next_state) = attack_result {
-                queue.push_back((strategy, next_state));
-            } else {
-                min = Some((cost + spell.cost, strategy));
-            }
+impl<'a> Problem for Battle<'a> {
+    type State = AttackResult;
+    type Key = ();
+
+    fn successors(&self, result: &AttackResult) -> Vec<(usize, AttackResult)> {
+        match *result {
+            Continue(ref state) => self.spells.iter()
+                .map(|&spell| (spell.cost as usize, cast_spell(state, spell, self.hard)))
+                .filter(|&(_, ref result)| !matches!(*result, Lose))
+                .collect(),
+            _ => vec![]
         }
     }
 
-    match min {
-        None => None,
-        Some((_, mut strategy)) => {
-            strategy.reverse();
-            Some(strategy)
-        }
+    fn is_goal(&self, result: &AttackResult) -> bool {
+        matches!(*result, Win)
     }
 }
 
+fn cheapest_strategy(spells: &[Spell], start: &State, hard: bool) -> Option<usize> {
+    let battle = Battle {spells, hard};
+
+    search::a_star(&battle, Continue(start.clone())).solution
+    .map(|solution| solution.cost)
+}
+
 fn main() {
     let spells = vec![
         Spell {
@@ -212,11 +206,7 @@ fn main() {
     };
 
     let start = State {player, boss, effects: Vec::new()};
-    let cost = |strategy: &Vec<Spell>| strategy.iter().map(|spell| spell.cost).sum::<i32>();
-
-    let strategy = cheapest_strategy(&spells, &start, false).unwrap();
-    println!("Part 1: {}", cost(&strategy));
 
-    let strategy = cheapest_strategy(&spells, &start, true).unwrap();
-    println!("Part 2: {}", cost(&strategy));
+    println!("Part 1: {}", cheapest_strategy(&spells, &start, false).unwrap());
+    println!("Part 2: {}", cheapest_strategy(&spells, &start, true).unwrap());
 }
@@ -4,6 +4,11 @@ use std::io::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
+#[path = "../2019/graph.rs"]
+mod graph;
+
+use graph::Graph as _;
+
 fn get_input() -> std::io::Result<String> {
     let mut file = File::open("07.txt")?;
     let mut contents = String::new();
@@ -14,6 +19,13 @@ fn get_input() -> std::io::Result<String> {
 
 type Graph<T> = HashMap<T, HashSet<T>>;
 
+impl<T> graph::Graph<T> for Graph<T>
+where T: Eq + Hash + Copy {
+    fn get_neighbors(&self, vertex: T) -> Vec<T> {
+        self.get(&vertex).map(|children| children.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
 struct Timetable<'a, T: 'a>
 where T: Hash + Eq {
     durations: &'a HashMap<T, usize>,
@@ -114,26 +126,11 @@ where T: Eq + Hash + Copy {
     .collect()
 }
 
-fn get_order<T>(mut graph: Graph<T>) -> Vec<T>
+fn get_order<T>(graph: &Graph<T>) -> Result<Vec<T>, Vec<T>>
 where T: Eq + Hash + Copy + Ord {
-    let mut result = vec![];
-    let mut root_nodes;
-
-    loop {
-        root_nodes = get_root_nodes(&graph);
-        root_nodes.sort();
-
-        if root_nodes.len() == 0 {
-            break;
-        }
-
-        let node = root_nodes.remove(0);
+    let steps = graph.keys().cloned().collect::<Vec<_>>();
 
-        graph.remove(&node);
-        result.push(node);
-    }
-
-    result
+    graph.topological_sort(&steps)
 }
 
 fn create_timetable<T>(mut graph: Graph<T>, durations: &HashMap<T, usize>, workers: usize) -> Timetable<T>
@@ -197,7 +194,13 @@ where T: Eq + Hash + Copy + Ord {
 fn main() {
     let input = get_input().unwrap();
     let graph = parse_input(&input);
-    let order = get_order(graph.clone());
+    let order = match get_order(&graph) {
+        Ok(order) => order,
+        Err(cycle) => {
+            println!("Failure: steps {:?} depend on each other", cycle);
+            return;
+        }
+    };
 
     println!("Part 1: {}", order.into_iter().fold(String::new(), |mut acc, x| {
         acc.push(x);
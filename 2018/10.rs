@@ -2,6 +2,9 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::collections::HashSet;
 
+#[path = "../2019/ocr.rs"]
+mod ocr;
+
 fn get_input() -> std::io::Result<String> {
     let mut file = File::open("10.txt")?;
     let mut contents = String::new();
@@ -71,7 +74,7 @@ fn main() {
     let input = get_input().unwrap();
     let points = parse(&input);
 
-    let (t, grid) = (0..)
+    let (t, message) = (0..)
         .map(|t| {
             points.iter()
             .map(|p| p.ticks(t).pos)
@@ -91,9 +94,12 @@ fn main() {
             ].iter().all(|p| !points.contains(p))
         }))
         .next()
-        .map(|(t, points)| (t, draw_grid(&points)))
-        .unwrap_or_else(|| (0, String::new()));
+        .unwrap_or_else(|| (0, HashSet::new()));
+
+    match ocr::recognize(message.iter().map(|&(x, y)| (x as i64, y as i64))) {
+        Ok(text) => println!("Part 1: {}", text),
+        Err(err) => println!("Part 1:\n{}\nFailure: {}", draw_grid(&message), err)
+    }
 
-    println!("Part 1:\n{}", grid);
     println!("Part 2: {}", t);
 }
@@ -1,7 +1,11 @@
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::{HashMap, HashSet, BinaryHeap};
-use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[path = "../2019/graph.rs"]
+mod graph;
+
+use graph::WeightedGraph;
 
 fn get_input() -> std::io::Result<String> {
     let mut file = File::open("22.txt")?;
@@ -21,15 +25,6 @@ enum Gear {
     Neither
 }
 
-#[derive(Debug, Hash, Copy, Clone, Eq, PartialEq, Ord)]
-struct State(usize, Point, Gear);
-
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some((-(self.0 as isize), self.1, self.2).cmp(&(-(other.0 as isize), other.1, other.2)))
-    }
-}
-
 fn parse(input: &str) -> Option<(isize, Point)> {
     let mut numbers = input.lines()
         .filter_map(|line| line.split(": ").nth(1))
@@ -88,40 +83,29 @@ fn get_usable_gear(region_type: isize) -> Vec<Gear> {
     }
 }
 
-fn get_fastest_route(map: &Map, target: Point) -> Option<usize> {
-    let mut heap = BinaryHeap::new();
-    let mut visited = HashSet::new();
-
-    heap.push(State(0, (0, 0), Gear::Torch));
-
-    while let Some(state) = heap.pop() {
-        if visited.contains(&(state.1, state.2)) {
-            continue;
-        } else {
-            visited.insert((state.1, state.2));
-        }
-
-        if state.1 == target && state.2 == Gear::Torch {
-            return Some(state.0);
-        }
-
-        let next_states = get_neighbors(map, state.1).into_iter()
-            .filter(|pos| get_usable_gear(map[pos]).contains(&state.2))
-            .map(|pos| State(state.0 + 1, pos, state.2))
+impl WeightedGraph<(Point, Gear)> for Map {
+    fn get_weighted_neighbors(&self, (pos, gear): (Point, Gear)) -> Vec<((Point, Gear), usize)> {
+        get_neighbors(self, pos).into_iter()
+            .filter(|p| get_usable_gear(self[p]).contains(&gear))
+            .map(|p| ((p, gear), 1))
             .chain(
-                get_usable_gear(map[&state.1])
+                get_usable_gear(self[&pos])
                 .into_iter()
-                .filter(|&gear| gear != state.2)
-                .map(|gear| State(state.0 + 7, state.1, gear))
+                .filter(|&g| g != gear)
+                .map(|g| ((pos, g), 7))
             )
-            .collect::<Vec<_>>();
-
-        for next in next_states.into_iter() {
-            heap.push(next);
-        }
+            .collect()
     }
+}
+
+fn get_fastest_route(map: &Map, target: Point) -> Option<usize> {
+    let heuristic = |&((x, y), gear): &(Point, Gear)| {
+        ((x - target.0).abs() + (y - target.1).abs()) as usize
+        + if gear == Gear::Torch { 0 } else { 7 }
+    };
 
-    None
+    map.a_star(((0, 0), Gear::Torch), (target, Gear::Torch), heuristic)
+    .map(|(cost, _)| cost)
 }
 
 fn main() {
@@ -1,6 +1,11 @@
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+
+#[path = "../2019/graph.rs"]
+mod graph;
+
+use graph::Graph;
 
 fn get_input() -> std::io::Result<String> {
     let mut file = File::open("25.txt")?;
@@ -13,6 +18,12 @@ fn get_input() -> std::io::Result<String> {
 type Coordinate = (isize, isize, isize, isize);
 type ConstellationGraph = HashMap<Coordinate, Vec<Coordinate>>;
 
+impl Graph<Coordinate> for ConstellationGraph {
+    fn get_neighbors(&self, coord: Coordinate) -> Vec<Coordinate> {
+        self.get(&coord).cloned().unwrap_or_default()
+    }
+}
+
 fn parse(input: &str) -> Vec<Coordinate> {
     input.lines()
     .map(|line| line.split(',').filter_map(|x| x.parse::<isize>().ok()).collect::<Vec<_>>())
@@ -40,37 +51,11 @@ fn get_constellation_graph(coordinates: &[Coordinate]) -> ConstellationGraph {
     })
 }
 
-fn get_connected_components(graph: &ConstellationGraph) -> HashMap<Coordinate, Coordinate> {
-    let mut remaining_vertices = graph.keys().cloned().collect::<HashSet<_>>();
-    let mut pivots = HashMap::new();
-
-    while remaining_vertices.len() > 0 {
-        let pivot = remaining_vertices.iter().next().cloned().unwrap();
-        let mut stack = vec![pivot];
-
-        while let Some(v) = stack.pop() {
-            if !remaining_vertices.contains(&v) {
-                continue;
-            }
-
-            pivots.insert(v, pivot);
-            remaining_vertices.remove(&v);
-
-            for &neighbor in graph[&v].iter() {
-                stack.push(neighbor);
-            }
-        }
-    }
-
-    pivots
-}
-
 fn main() {
     let input = get_input().unwrap();
     let coordinates = parse(&input);
     let graph = get_constellation_graph(&coordinates);
-    let components = get_connected_components(&graph);
-    let components_count = components.values().collect::<HashSet<_>>().len();
+    let components_count = graph.connected_components(&coordinates).len();
 
     println!("Part 1: {}", components_count);
 }
@@ -10,13 +10,13 @@ fn get_input() -> std::io::Result<String> {
 }
 
 fn get_fuel(mass: u64) -> u64 {
-  (mass / 3).checked_sub(2).unwrap_or(0)
+  (mass / 3).saturating_sub(2)
 }
 
 fn get_fuel_recursive(mass: u64) -> u64 {
   let initial_fuel = get_fuel(mass);
 
-  if initial_fuel <= 0 {
+  if initial_fuel == 0 {
     0
   } else {
     initial_fuel + get_fuel_recursive(initial_fuel)
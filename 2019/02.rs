@@ -1,6 +1,8 @@
 use std::fs::File;
 use std::io::prelude::*;
 
+use intcode::*;
+
 fn get_input() -> std::io::Result<String> {
   let mut file = File::open("02.txt")?;
   let mut contents = String::new();
@@ -9,41 +11,20 @@ fn get_input() -> std::io::Result<String> {
   Ok(contents)
 }
 
-fn run_program(program: &mut Vec<usize>) {
-  let mut pointer = 0;
-
-  loop {
-    let target_value = {
-      let get = |p| program[program[p]];
-
-      match program[pointer] {
-        1 => get(pointer + 1) + get(pointer + 2),
-        2 => get(pointer + 1) * get(pointer + 2),
-        99 => break,
-        _ => panic!()
-      }
-    };
-
-    let target_index = program[pointer + 3];
-    program[target_index] = target_value;
-
-    pointer += 4;
-  }
-}
-
-fn calculate_output(mut program: Vec<usize>, input: (usize, usize)) -> usize {
-  program[1] = input.0;
-  program[2] = input.1;
+fn calculate_output(program: Vec<i64>, input: (i64, i64)) -> i64 {
+  let mut state = Machine::new(program);
+  state.memory[1] = input.0;
+  state.memory[2] = input.1;
 
-  run_program(&mut program);
+  run_program(&mut state, None).unwrap();
 
-  program[0]
+  state.memory[0]
 }
 
 fn main() {
   let input = get_input().unwrap();
   let program = input.split(',')
-    .filter_map(|x| x.trim().parse::<usize>().ok())
+    .filter_map(|x| x.trim().parse::<i64>().ok())
     .collect::<Vec<_>>();
 
   println!("Part 1: {}", calculate_output(program.clone(), (12, 2)));
@@ -31,7 +31,7 @@ fn parse_wires(input: &str) -> Vec<Wire> {
     line.split(',')
     .filter_map(|token| {
       let steps = token.get(1..).and_then(|steps| steps.parse::<usize>().ok());
-      let direction = match token.chars().nth(0) {
+      let direction = match token.chars().next() {
         Some('L') => Some(Direction::Left),
         Some('R') => Some(Direction::Right),
         Some('U') => Some(Direction::Up),
@@ -65,15 +65,11 @@ fn trace_wire(grid: &mut Grid, id: usize, wire: &Wire) {
       position = (position.0 + move_vector.0, position.1 + move_vector.1);
       timestamp += 1;
 
-      if !grid.contains_key(&position) {
-        grid.insert(position, HashMap::new());
-      }
+      grid.entry(position).or_default();
 
       let ids = grid.get_mut(&position).unwrap();
 
-      if !ids.contains_key(&id) {
-        ids.insert(id, timestamp);
-      }
+      ids.entry(id).or_insert(timestamp);
     }
   }
 }
@@ -103,16 +99,11 @@ fn main() {
     trace_wire(&mut grid, i, wire);
   }
 
-  iter_collisions(&grid)
-  .map(|pos| norm(pos))
-  .min()
-  .map(|min_collision_norm| {
+  if let Some(min_collision_norm) = iter_collisions(&grid).map(norm).min() {
     println!("Part 1: {}", min_collision_norm);
-  });
+  }
 
-  iter_collision_times(&grid)
-  .min()
-  .map(|min_collision_time| {
+  if let Some(min_collision_time) = iter_collision_times(&grid).min() {
     println!("Part 2: {}", min_collision_time);
-  });
+  }
 }
@@ -1,8 +1,11 @@
 use std::fs::File;
 use std::io::prelude::*;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
+mod graph;
+use graph::Graph;
+
 #[derive(Debug)]
 struct OrbitMap<T: Hash + Eq> {
   objects: HashSet<T>,
@@ -32,6 +35,14 @@ impl<T: Copy + Hash + Eq> OrbitMap<T> {
   }
 }
 
+impl<T: Copy + Hash + Eq> Graph<T> for OrbitMap<T> {
+  fn get_neighbors(&self, object: T) -> Vec<T> {
+    self.children.get(&object).cloned().unwrap_or_default().into_iter()
+    .chain(self.parents.get(&object).cloned())
+    .collect()
+  }
+}
+
 fn get_input() -> std::io::Result<String> {
   let mut file = File::open("06.txt")?;
   let mut contents = String::new();
@@ -45,7 +56,7 @@ fn parse_input(input: &str) -> OrbitMap<&str> {
   .filter_map(|line| {
     let mut ids = line.split(')');
 
-    match (ids.nth(0), ids.nth(0)) {
+    match (ids.next(), ids.next()) {
       (Some(parent), Some(child)) => Some((parent, child)),
       _ => None
     }
@@ -68,48 +79,6 @@ fn get_orbits<T: Copy + Hash + Eq>(orbit_map: &OrbitMap<T>, object: T) -> Vec<T>
   result
 }
 
-fn bfs<T: Copy + Hash + Eq>(orbit_map: &OrbitMap<T>, start: T, target: T) -> Option<Vec<T>> {
-  let mut previous_map = HashMap::new();
-  previous_map.insert(start, None);
-
-  let mut queue = VecDeque::new();
-  queue.push_back(start);
-
-  while let Some(current) = queue.pop_front() {
-    if current == target {
-      break;
-    }
-
-    let parent_list = [orbit_map.parents.get(&current)];
-    let next_list = orbit_map.children.get(&current)
-      .map(|children| children.iter().chain(
-        parent_list.into_iter().filter_map(|&x| x)
-      ));
-
-    if let Some(next_list) = next_list {
-      for &next in next_list {
-        if !previous_map.contains_key(&next) {
-          previous_map.insert(next, Some(current));
-          queue.push_back(next);
-        }
-      }
-    }
-  }
-
-  if let None = previous_map.get(&target) {
-    return None;
-  }
-
-  let mut path = vec![target];
-
-  while let Some(&Some(previous)) = previous_map.get(path.last().unwrap()) {
-    path.push(previous);
-  }
-
-  path.reverse();
-  Some(path)
-}
-
 fn main() {
   let input = get_input().unwrap();
   let orbit_map = parse_input(&input);
@@ -122,7 +91,7 @@ fn main() {
 
   let me_orbit = orbit_map.parents["YOU"];
   let santa_orbit = orbit_map.parents["SAN"];
-  let path = bfs(&orbit_map, me_orbit, santa_orbit).unwrap();
+  let path = orbit_map.bfs(me_orbit).construct_path(santa_orbit).unwrap();
 
   println!("Part 2: {}", path.len() - 1);
 }
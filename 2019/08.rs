@@ -2,6 +2,8 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::collections::HashMap;
 
+mod ocr;
+
 type Layer = HashMap<(usize, usize), u8>;
 
 struct LayerIter<T: Iterator<Item = u8>> {
@@ -109,6 +111,12 @@ fn main() {
   println!("Part 1: {}", ones * twos);
 
   let merged_layer = merge_layers(parse_layers(&input, width, height));
+  let lit_pixels = merged_layer.iter()
+    .filter(|&(_, &pixel)| pixel == 1)
+    .map(|(&(x, y), _)| (x as i64, y as i64));
 
-  println!("Part 2:\n{}", render(merged_layer, width, height));
+  match ocr::recognize(lit_pixels) {
+    Ok(text) => println!("Part 2: {}", text),
+    Err(err) => println!("Part 2:\n{}\nFailure: {}", render(merged_layer, width, height), err)
+  }
 }
@@ -1,7 +1,6 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-mod intcode;
 use intcode::*;
 
 fn get_input() -> std::io::Result<String> {
@@ -18,11 +17,11 @@ fn main() {
     .filter_map(|x| x.trim().parse::<i64>().ok())
     .collect::<Vec<_>>();
 
-  let mut state = ProgramState::new(numbers.clone());
-  let (outputs, _) = run_program_with_inputs(&mut state, std::iter::once(1));
+  let mut state = Machine::new(numbers.clone());
+  let (outputs, _) = run_program_with_inputs(&mut state, std::iter::once(1)).unwrap();
   println!("Part 1: {}", outputs.last().unwrap());
 
-  let mut state = ProgramState::new(numbers.clone());
-  let (outputs, _) = run_program_with_inputs(&mut state, std::iter::once(2));
+  let mut state = Machine::new(numbers.clone());
+  let (outputs, _) = run_program_with_inputs(&mut state, std::iter::once(2)).unwrap();
   println!("Part 2: {}", outputs.last().unwrap());
 }
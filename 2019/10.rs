@@ -83,7 +83,7 @@ fn has_direct_sight_line(obstacles: &HashSet<Point>, distance: i32, p1: Point, p
 
 fn get_angles(obstacles: &HashSet<Point>, p: Point) -> Vec<Point> {
   fn clockwise_up_atan2(dx: f32, dy: f32) -> f32 {
-    std::f32::consts::PI - (dx as f32).atan2(dy as f32)
+    std::f32::consts::PI - dx.atan2(dy)
   }
 
   let mut angles = obstacles.iter()
@@ -106,7 +106,7 @@ fn main() {
 
   let max_distance = 2 * asteroid_positions.iter()
     .map(|&(x, y)| {
-      [x.abs(), y.abs()].into_iter().max().cloned().unwrap()
+      [x.abs(), y.abs()].iter().max().cloned().unwrap()
     })
     .max()
     .unwrap_or(0);
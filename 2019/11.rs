@@ -1,299 +1,228 @@
-use std::fs::File;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::collections::HashMap;
 
-#[derive(Debug)]
-enum ParameterMode {
-  Position,
-  Immediate,
-  Relative
-}
+mod ocr;
 
-#[derive(Debug)]
-enum OperationType {
-  Add,
-  Multiply,
-  Input,
-  Output,
-  JumpIfTrue,
-  JumpIfFalse,
-  LessThan,
-  Equals,
-  SetRelativeBase,
-  Halt
+use intcode::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Color {
+  Black,
+  White
 }
 
 #[derive(Debug)]
-struct Instruction {
-  operation: OperationType,
-  inputs: Vec<(ParameterMode, i64)>
+enum RobotError {
+  Vm(VmError),
+  InvalidOutput(i64),
+  MissingTurn
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum ProgramResult {
-  Output(i64),
-  WaitForInput,
-  Halt,
+impl fmt::Display for RobotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RobotError::Vm(err) => write!(f, "{}", err),
+      RobotError::InvalidOutput(x) => write!(f, "invalid robot output {}", x),
+      RobotError::MissingTurn => write!(f, "robot painted without turning")
+    }
+  }
 }
 
-#[derive(Debug, Copy, Clone)]
-enum Color {
-  Black,
-  White
+impl From<VmError> for RobotError {
+  fn from(err: VmError) -> RobotError {
+    RobotError::Vm(err)
+  }
 }
 
-#[derive(Debug)]
-enum BotInstruction {
-  TurnLeft,
-  TurnRight
+/// Panels of the hull. Panels that were never painted are black.
+#[derive(Debug, Default)]
+struct Hull {
+  panels: HashMap<(i32, i32), Color>
 }
 
-type Hull = HashMap<(i32, i32), Color>;
+impl Hull {
+  fn get(&self, position: (i32, i32)) -> Color {
+    self.panels.get(&position).cloned().unwrap_or(Color::Black)
+  }
 
-fn get_input() -> std::io::Result<String> {
-  let mut file = File::open("11.txt")?;
-  let mut contents = String::new();
-  file.read_to_string(&mut contents)?;
+  fn paint(&mut self, position: (i32, i32), color: Color) {
+    self.panels.insert(position, color);
+  }
 
-  Ok(contents)
-}
+  fn painted_count(&self) -> usize {
+    self.panels.len()
+  }
 
-fn parse_instruction(numbers: &[i64]) -> Instruction {
-  let instruction_code = numbers[0];
-  let op_code = instruction_code % 100;
-
-  let (operation, inputs_count) = match op_code {
-    1 => (OperationType::Add, 3),
-    2 => (OperationType::Multiply, 3),
-    3 => (OperationType::Input, 1),
-    4 => (OperationType::Output, 1),
-    5 => (OperationType::JumpIfTrue, 2),
-    6 => (OperationType::JumpIfFalse, 2),
-    7 => (OperationType::LessThan, 3),
-    8 => (OperationType::Equals, 3),
-    9 => (OperationType::SetRelativeBase, 1),
-    99 => (OperationType::Halt, 0),
-    _ => panic!()
-  };
-
-  let get_parameter_mode = |i| {
-    match (instruction_code - op_code) / 10i64.pow(2 + i as u32) % 10 {
-      0 => ParameterMode::Position,
-      1 => ParameterMode::Immediate,
-      2 => ParameterMode::Relative,
-      _ => panic!()
+  /// Returns the painted area as rows of pixels, `true` being white.
+  fn pixels(&self) -> Vec<Vec<bool>> {
+    let bounds = self.panels.keys().cloned()
+      .fold((None, None, None, None), |(min_x, max_x, min_y, max_y), (x, y)| {
+        (
+          [min_x, Some(x)].iter().filter_map(|&x| x).min(),
+          [max_x, Some(x)].iter().filter_map(|&x| x).max(),
+          [min_y, Some(y)].iter().filter_map(|&y| y).min(),
+          [max_y, Some(y)].iter().filter_map(|&y| y).max()
+        )
+      });
+
+    match bounds {
+      (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) => (min_y..=max_y)
+        .map(|y| (min_x..=max_x).map(|x| self.get((x, y)) == Color::White).collect())
+        .collect(),
+      _ => vec![]
     }
-  };
+  }
 
-  Instruction {
-    operation,
-    inputs: (0..inputs_count)
-      .map(|i| (get_parameter_mode(i), numbers[i + 1]))
+  fn render(&self) -> String {
+    self.pixels().into_iter()
+      .map(|row| row.into_iter().map(|white| if white { '#' } else { ' ' }).chain(Some('\n')).collect::<String>())
       .collect()
   }
+
+  /// Encodes the painted area as a binary PGM image, every panel being
+  /// `scale` by `scale` pixels.
+  fn to_pgm(&self, scale: usize) -> Vec<u8> {
+    encode_image(&self.pixels(), scale, "P5", |white| vec![if white { 255 } else { 0 }])
+  }
+
+  /// Like `to_pgm`, but as a binary PPM image with white paint on a dark
+  /// blue hull.
+  fn to_ppm(&self, scale: usize) -> Vec<u8> {
+    encode_image(&self.pixels(), scale, "P6", |white| if white { vec![255, 255, 255] } else { vec![16, 24, 48] })
+  }
 }
 
-fn run_program(state: (&mut Vec<i64>, &mut usize, &mut usize), input: Option<i64>) -> ProgramResult {
-  let (program, pointer, relative_base) = state;
-  let mut input = input;
+fn encode_image<F: Fn(bool) -> Vec<u8>>(pixels: &[Vec<bool>], scale: usize, magic: &str, color: F) -> Vec<u8> {
+  let height = pixels.len() * scale;
+  let width = pixels.first().map_or(0, |row| row.len()) * scale;
+  let mut result = format!("{}\n{} {}\n255\n", magic, width, height).into_bytes();
 
-  fn extend_memory(program: &mut Vec<i64>, index: usize) {
-    while index >= program.len() {
-      program.push(0);
+  for row in pixels {
+    let line = row.iter()
+      .flat_map(|&white| vec![color(white); scale])
+      .flatten()
+      .collect::<Vec<_>>();
+
+    for _ in 0..scale {
+      result.extend(&line);
     }
   }
 
-  fn get_instruction_input_index(
-    program: &mut Vec<i64>,
-    instruction: &Instruction,
-    relative_base: usize,
-    index: usize
-  ) -> usize {
-    match instruction.inputs[index] {
-      (ParameterMode::Relative, d) => {
-        let j = (relative_base as i64 + d) as usize;
-        extend_memory(program, j);
-        j
-      },
-      (_, j) => {
-        extend_memory(program, j as usize);
-        j as usize
-      }
+  result
+}
+
+/// Runs an Intcode program as a painting robot: it reads the color of the
+/// panel below, outputs a color to paint and a direction to turn to, then
+/// moves forward one panel.
+struct Robot<M: Memory = PagedMemory> {
+  machine: Machine<M>,
+  position: (i32, i32),
+  direction: (i32, i32)
+}
+
+impl<M: Memory> Robot<M> {
+  fn new(machine: Machine<M>) -> Robot<M> {
+    Robot {
+      machine,
+      position: (0, 0),
+      direction: (0, -1)
     }
   }
 
-  fn get_instruction_input(
-    program: &mut Vec<i64>,
-    instruction: &Instruction,
-    relative_base: usize,
-    index: usize
-  ) -> i64 {
-    match instruction.inputs[index] {
-      (ParameterMode::Immediate, value) => value,
-      _ => {
-        let j = get_instruction_input_index(program, instruction, relative_base, index);
-        program[j]
-      }
+  fn next_output(&mut self, input: Option<i64>) -> Result<Option<i64>, RobotError> {
+    match run_program(&mut self.machine, input)? {
+      ProgramResult::Output(x) => Ok(Some(x)),
+      _ => Ok(None)
     }
   }
 
-  while *pointer < program.len() {
-    let init_pointer = *pointer;
-    let instruction = parse_instruction(&program[*pointer..]);
+  /// Paints `hull` until the program halts or waits for input without
+  /// having moved the robot.
+  fn run(&mut self, hull: &mut Hull) -> Result<(), RobotError> {
+    fn gauss_mul((a, b): (i32, i32), (c, d): (i32, i32)) -> (i32, i32) {
+      (a * c - b * d, a * d + b * c)
+    }
 
-    let (target_value, target_index) = {
-      let mut get_input = |i| {
-        get_instruction_input(program, &instruction, *relative_base, i)
+    loop {
+      let input = match hull.get(self.position) {
+        Color::Black => 0,
+        Color::White => 1
       };
 
-      let (target_value, output_index) = match instruction.operation {
-        OperationType::Add => (Some(get_input(0) + get_input(1)), Some(2)),
-        OperationType::Multiply => (Some(get_input(0) * get_input(1)), Some(2)),
-        OperationType::Input => match input {
-          Some(x) => {
-            input = None;
-            (Some(x), Some(0))
-          },
-          _ => return ProgramResult::WaitForInput
-        },
-        OperationType::Output => (Some(get_input(0)), None),
-        OperationType::LessThan => (Some((get_input(0) < get_input(1)) as i64), Some(2)),
-        OperationType::Equals => (Some((get_input(0) == get_input(1)) as i64), Some(2)),
-        OperationType::JumpIfTrue => {
-          if get_input(0) != 0 {
-            *pointer = get_input(1) as usize;
-          }
-
-          (None, None)
-        },
-        OperationType::JumpIfFalse => {
-          if get_input(0) == 0 {
-            *pointer = get_input(1) as usize;
-          }
-
-          (None, None)
-        },
-        OperationType::SetRelativeBase => {
-          *relative_base = (*relative_base as i64 + get_input(0)) as usize;
-          (None, None)
-        }
-        OperationType::Halt => break
+      let color = match self.next_output(Some(input))? {
+        Some(0) => Color::Black,
+        Some(1) => Color::White,
+        Some(x) => return Err(RobotError::InvalidOutput(x)),
+        None => return Ok(())
       };
 
-      (
-        target_value,
-        output_index.map(|i| {
-          get_instruction_input_index(program, &instruction, *relative_base, i)
-        })
-      )
-    };
-
-    if *pointer == init_pointer {
-      *pointer += instruction.inputs.len() + 1;
-    }
+      let rotation = match self.next_output(None)? {
+        Some(0) => (0, -1),
+        Some(1) => (0, 1),
+        Some(x) => return Err(RobotError::InvalidOutput(x)),
+        None => return Err(RobotError::MissingTurn)
+      };
 
-    if let Some(target_value) = target_value {
-      if let Some(target_index) = target_index {
-        extend_memory(program, target_index);
-        program[target_index] = target_value;
-      } else {
-        return ProgramResult::Output(target_value);
-      }
+      hull.paint(self.position, color);
+      self.direction = gauss_mul(self.direction, rotation);
+      self.position = (self.position.0 + self.direction.0, self.position.1 + self.direction.1);
     }
   }
-
-  ProgramResult::Halt
 }
 
-fn paint_emergency_hull(hull: &mut Hull, program: &mut Vec<i64>) {
-  let mut position = (0, 0);
-  let mut direction = (0, -1);
-  let mut state = (0, 0);
-
-  fn gauss_mul((a, b): (i32, i32), (c, d): (i32, i32)) -> (i32, i32) {
-    (a * c - b * d, a * d + b * c)
-  }
-
-  loop {
-    let input = match hull.get(&position) {
-      Some(&Color::White) => 1,
-      _ => 0
-    };
-
-    let color = match run_program((program, &mut state.0, &mut state.1), Some(input)) {
-      ProgramResult::Halt => break,
-      ProgramResult::WaitForInput => panic!(),
-      ProgramResult::Output(x) => match x {
-        0 => Color::Black,
-        1 => Color::White,
-        _ => panic!()
-      }
-    };
-
-    let instruction = match run_program((program, &mut state.0, &mut state.1), None) {
-      ProgramResult::Halt => break,
-      ProgramResult::WaitForInput => panic!(),
-      ProgramResult::Output(x) => match x {
-        0 => BotInstruction::TurnLeft,
-        1 => BotInstruction::TurnRight,
-        _ => panic!()
-      }
-    };
+fn get_input() -> std::io::Result<String> {
+  let mut file = File::open("11.txt")?;
+  let mut contents = String::new();
+  file.read_to_string(&mut contents)?;
 
-    hull.insert(position, color);
-    direction = match instruction {
-      BotInstruction::TurnLeft => gauss_mul(direction, (0, 1)),
-      BotInstruction::TurnRight => gauss_mul(direction, (0, -1))
-    };
+  Ok(contents)
+}
 
-    position = (position.0 + direction.0, position.1 + direction.1);
-  }
+fn get_arg(name: &str) -> Option<String> {
+  env::args().skip_while(|arg| arg != name).nth(1)
 }
 
-fn render_hull(hull: &Hull) -> String {
-  let mut result = String::new();
-  let bounds = hull.keys().cloned()
-    .fold((None, None, None, None), |(min_x, max_x, min_y, max_y), (x, y)| {
-      (
-        [min_x, Some(x)].into_iter().filter_map(|&x| x).min(),
-        [max_x, Some(x)].into_iter().filter_map(|&x| x).max(),
-        [min_y, Some(y)].into_iter().filter_map(|&y| y).min(),
-        [max_y, Some(y)].into_iter().filter_map(|&y| y).max()
-      )
-    });
-
-  if let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = bounds {
-    for y in min_y..=max_y {
-      for x in (min_x..=max_x).rev() {
-        let color = hull.get(&(x, y)).cloned().unwrap_or(Color::Black);
-
-        result.push(match color {
-          Color::Black => ' ',
-          Color::White => '#'
-        });
-      }
+fn main() {
+  let input = get_input().unwrap();
+  let program = parse_program(&input);
 
-      result.push('\n');
-    }
+  let mut hull = Hull::default();
+
+  match Robot::new(Machine::new(program.clone())).run(&mut hull) {
+    Ok(()) => println!("Part 1: {}", hull.painted_count()),
+    Err(err) => println!("Failure: {}", err)
   }
 
-  result
-}
+  let mut hull = Hull::default();
+  hull.paint((0, 0), Color::White);
 
-fn main() {
-  let input = get_input().unwrap();
-  let numbers = input.split(',')
-    .filter_map(|x| x.trim().parse::<i64>().ok())
-    .collect::<Vec<_>>();
+  if let Err(err) = Robot::new(Machine::new(program)).run(&mut hull) {
+    println!("Failure: {}", err);
+    return;
+  }
 
-  let mut hull = Hull::new();
-  paint_emergency_hull(&mut hull, &mut numbers.clone());
+  let white_panels = hull.panels.iter()
+    .filter(|&(_, &color)| color == Color::White)
+    .map(|(&(x, y), _)| (x as i64, y as i64));
 
-  println!("Part 1: {}", hull.len());
+  match ocr::recognize(white_panels) {
+    Ok(text) => println!("Part 2: {}", text),
+    Err(err) => println!("Part 2:\n{}\nFailure: {}", hull.render(), err)
+  }
 
-  let mut hull = Hull::new();
-  hull.insert((0, 0), Color::White);
-  paint_emergency_hull(&mut hull, &mut numbers.clone());
+  let images = [
+    ("--pgm", hull.to_pgm(8)),
+    ("--ppm", hull.to_ppm(8))
+  ];
 
-  println!("Part 2:\n{}", render_hull(&hull));
+  for (flag, image) in &images {
+    if let Some(path) = get_arg(flag) {
+      if let Err(err) = fs::write(&path, image) {
+        println!("Failure: {}: {}", path, err);
+      }
+    }
+  }
 }
@@ -58,7 +58,7 @@ fn parse_input(input: &str) -> Vec<MoonState> {
         }
       });
 
-    match (coords.nth(0), coords.nth(0), coords.nth(0)) {
+    match (coords.next(), coords.next(), coords.next()) {
       (Some(x), Some(y), Some(z)) => Some((x, y, z)),
       _ => None
     }
@@ -125,7 +125,7 @@ fn calc_acceleration(pos1: Vector3, pos2: Vector3) -> Vector3 {
   )
 }
 
-fn simulate_tick(moons: &mut Vec<MoonState>) {
+fn simulate_tick(moons: &mut [MoonState]) {
   // Apply gravity
 
   let n = moons.len();
@@ -183,8 +183,8 @@ fn main() {
   fn count_var<F, T>(
     counter: &mut i64,
     finished: &mut bool,
-    moons: &Vec<MoonState>,
-    compare: &Vec<MoonState>,
+    moons: &[MoonState],
+    compare: &[MoonState],
     predicate: F
   )
   where
@@ -1,10 +1,10 @@
-use std::fs::File;
-use std::io::prelude::*;
+use std::fs::{self, File};
+use std::io::{self, prelude::*};
 use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver};
 use std::{fmt, env, thread, time};
 
-mod intcode;
-use intcode::{run_program, ProgramResult, ProgramState};
+use intcode::{run_program, ProgramResult, Machine, VmError};
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum Tile {
@@ -29,6 +29,29 @@ impl fmt::Display for Tile {
 
 type TileGrid = HashMap<(i64, i64), Tile>;
 
+#[derive(Debug)]
+enum GameError {
+  Vm(VmError),
+  InvalidTile(i64),
+  IncompleteOutput
+}
+
+impl fmt::Display for GameError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GameError::Vm(err) => write!(f, "{}", err),
+      GameError::InvalidTile(id) => write!(f, "invalid tile id {}", id),
+      GameError::IncompleteOutput => write!(f, "program stopped in the middle of drawing a tile")
+    }
+  }
+}
+
+impl From<VmError> for GameError {
+  fn from(err: VmError) -> GameError {
+    GameError::Vm(err)
+  }
+}
+
 fn get_input() -> std::io::Result<String> {
   let mut file = File::open("13.txt")?;
   let mut contents = String::new();
@@ -37,7 +60,7 @@ fn get_input() -> std::io::Result<String> {
   Ok(contents)
 }
 
-fn output_screen(state: &mut ProgramState, input: Option<i64>) -> (TileGrid, Option<i64>, bool) {
+fn output_screen(state: &mut Machine, input: Option<i64>) -> Result<(TileGrid, Option<i64>, bool), GameError> {
   let mut result = TileGrid::new();
   let mut score = None;
   let mut input_iter = input.into_iter();
@@ -46,7 +69,7 @@ fn output_screen(state: &mut ProgramState, input: Option<i64>) -> (TileGrid, Opt
   loop {
     let mut get = |input| run_program(state, input);
 
-    match (get(input_iter.next()), get(None), get(None)) {
+    match (get(input_iter.next())?, get(None)?, get(None)?) {
       (ProgramResult::Output(x), ProgramResult::Output(y), ProgramResult::Output(value)) => {
         if (x, y) == (-1, 0) {
           score = Some(value);
@@ -57,7 +80,7 @@ fn output_screen(state: &mut ProgramState, input: Option<i64>) -> (TileGrid, Opt
             2 => Tile::Block,
             3 => Tile::HorizontalPaddle,
             4 => Tile::Ball,
-            _ => panic!()
+            id => return Err(GameError::InvalidTile(id))
           });
         }
       },
@@ -66,11 +89,11 @@ fn output_screen(state: &mut ProgramState, input: Option<i64>) -> (TileGrid, Opt
         halted = true;
         break;
       },
-      _ => panic!()
+      _ => return Err(GameError::IncompleteOutput)
     };
   }
 
-  (result, score, halted)
+  Ok((result, score, halted))
 }
 
 fn render_screen(screen: &TileGrid) -> String {
@@ -78,10 +101,10 @@ fn render_screen(screen: &TileGrid) -> String {
   let bounds = screen.keys().cloned()
     .fold((None, None, None, None), |(min_x, max_x, min_y, max_y), (x, y)| {
       (
-        [min_x, Some(x)].into_iter().filter_map(|&x| x).min(),
-        [max_x, Some(x)].into_iter().filter_map(|&x| x).max(),
-        [min_y, Some(y)].into_iter().filter_map(|&y| y).min(),
-        [max_y, Some(y)].into_iter().filter_map(|&y| y).max()
+        [min_x, Some(x)].iter().filter_map(|&x| x).min(),
+        [max_x, Some(x)].iter().filter_map(|&x| x).max(),
+        [min_y, Some(y)].iter().filter_map(|&y| y).min(),
+        [max_y, Some(y)].iter().filter_map(|&y| y).max()
       )
     });
 
@@ -100,41 +123,198 @@ fn render_screen(screen: &TileGrid) -> String {
   result
 }
 
-fn play_game(state: &mut ProgramState, print: bool) -> i64 {
-  fn get_tile_position(screen: &TileGrid, tile: Tile) -> (i64, i64) {
-    screen.iter()
-    .find(|&(_, &t)| t == tile)
-    .map(|(&position, _)| position)
-    .unwrap()
+fn get_tile_position(screen: &TileGrid, tile: Tile) -> Option<(i64, i64)> {
+  screen.iter()
+  .find(|&(_, &t)| t == tile)
+  .map(|(&position, _)| position)
+}
+
+/// Controller for the joystick, asked once per frame.
+trait Policy {
+  fn name(&self) -> &str;
+  fn joystick(&mut self, screen: &TileGrid) -> i64;
+}
+
+/// Keeps the paddle below the ball.
+struct FollowBall;
+
+impl Policy for FollowBall {
+  fn name(&self) -> &str {
+    "follow ball"
+  }
+
+  fn joystick(&mut self, screen: &TileGrid) -> i64 {
+    match (get_tile_position(screen, Tile::HorizontalPaddle), get_tile_position(screen, Tile::Ball)) {
+      (Some(paddle), Some(ball)) => (ball.0 - paddle.0).signum(),
+      _ => 0
+    }
+  }
+}
+
+/// Moves the paddle to where the ball will reach the paddle row, taking
+/// bounces off the side walls into account. As long as blocks could still
+/// deflect the ball, it follows the ball instead.
+#[derive(Default)]
+struct PredictTrajectory {
+  previous_ball: Option<(i64, i64)>
+}
+
+impl Policy for PredictTrajectory {
+  fn name(&self) -> &str {
+    "predict trajectory"
+  }
+
+  fn joystick(&mut self, screen: &TileGrid) -> i64 {
+    let paddle = get_tile_position(screen, Tile::HorizontalPaddle);
+    let ball = get_tile_position(screen, Tile::Ball);
+    let previous_ball = self.previous_ball;
+
+    self.previous_ball = ball;
+
+    let (paddle, ball) = match (paddle, ball) {
+      (Some(paddle), Some(ball)) => (paddle, ball),
+      _ => return 0
+    };
+
+    let lowest_block = screen.iter()
+      .filter(|&(_, &tile)| tile == Tile::Block)
+      .map(|(&(_, y), _)| y)
+      .max()
+      .unwrap_or(0);
+
+    // The ball bounces between the innermost columns next to the walls.
+    // Until walls on both sides have been drawn, there's nothing to bounce
+    // off, so it just follows the ball.
+    let columns = screen.iter()
+      .filter(|&(_, &tile)| tile == Tile::Wall)
+      .map(|(&(x, _), _)| x)
+      .fold(None, |bounds, x| match bounds {
+        Some((min_x, max_x)) => Some((x.min(min_x), x.max(max_x))),
+        None => Some((x, x))
+      })
+      .filter(|&(min_x, max_x)| max_x - min_x >= 2)
+      .map(|(min_x, max_x)| (min_x + 1, max_x - min_x - 2));
+
+    let target = match (previous_ball, columns) {
+      (Some(previous), Some((low, width))) if ball.1 > previous.1 && ball.1 > lowest_block => {
+        let x = ball.0 + (ball.0 - previous.0) * (paddle.1 - 1 - ball.1) - low;
+
+        match width {
+          0 => low,
+          _ => {
+            let x = x.rem_euclid(2 * width);
+            low + if x > width { 2 * width - x } else { x }
+          }
+        }
+      },
+      _ => ball.0
+    };
+
+    (target - paddle.0).signum()
+  }
+}
+
+/// Steers with lines typed on stdin: `a` moves left, `d` moves right and
+/// `s` stops. The last command stays in effect until the next one. Without
+/// a raw terminal mode, stdin only delivers whole lines, so every command
+/// has to be confirmed with Enter; the game doesn't react to single keys.
+struct Keyboard {
+  commands: Receiver<String>,
+  joystick: i64
+}
+
+impl Keyboard {
+  fn new() -> Keyboard {
+    let (sender, commands) = channel();
+
+    thread::spawn(move || {
+      let stdin = io::stdin();
+
+      for line in stdin.lock().lines().map_while(Result::ok) {
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+
+    Keyboard { commands, joystick: 0 }
+  }
+}
+
+impl Policy for Keyboard {
+  fn name(&self) -> &str {
+    "keyboard"
+  }
+
+  fn joystick(&mut self, _: &TileGrid) -> i64 {
+    while let Ok(line) = self.commands.try_recv() {
+      self.joystick = match line.trim() {
+        "a" => -1,
+        "d" => 1,
+        "s" => 0,
+        _ => self.joystick
+      };
+    }
+
+    self.joystick
   }
+}
+
+#[derive(Debug, Clone)]
+struct GameResult {
+  score: i64,
+  frames: usize,
+  /// Frames in which the paddle changed position.
+  paddle_moves: usize,
+  won: bool
+}
+
+fn render_frame(screen: &TileGrid, score: i64, frame: usize) -> String {
+  format!("// Frame: {}, Score: {}\n{}\n", frame, score, render_screen(screen))
+}
 
-  let (mut screen, mut score, _) = output_screen(state, None);
+/// Plays until the program halts, calling `on_frame` after every frame.
+fn play_game<P, F>(state: &mut Machine, policy: &mut P, mut on_frame: F) -> Result<GameResult, GameError>
+where P: Policy + ?Sized, F: FnMut(&TileGrid, i64, usize) {
+  let (mut screen, mut score, _) = output_screen(state, None)?;
+  let mut frames = 0;
+  let mut paddle_moves = 0;
 
   loop {
-    let paddle_position = get_tile_position(&screen, Tile::HorizontalPaddle);
-    let ball_position = get_tile_position(&screen, Tile::Ball);
-    let joystick = (ball_position.0 - paddle_position.0).signum();
-    let (screen_update, score_update, halted) = output_screen(state, Some(joystick));
+    let paddle = get_tile_position(&screen, Tile::HorizontalPaddle);
+    let joystick = policy.joystick(&screen);
+    let (screen_update, score_update, halted) = output_screen(state, Some(joystick))?;
 
     for (&(x, y), &tile) in screen_update.iter() {
       screen.insert((x, y), tile);
     }
 
-    if let Some(_) = score_update {
+    if score_update.is_some() {
       score = score_update;
     }
 
-    if print {
-      println!("// Score: {}\n{}\n", score.unwrap_or(0), render_screen(&screen));
-      thread::sleep(time::Duration::from_millis(50));
+    if get_tile_position(&screen, Tile::HorizontalPaddle) != paddle {
+      paddle_moves += 1;
     }
 
+    frames += 1;
+    on_frame(&screen, score.unwrap_or(0), frames);
+
     if halted {
       break;
     }
   }
 
-  score.unwrap_or(0)
+  Ok(GameResult {
+    score: score.unwrap_or(0),
+    frames,
+    paddle_moves,
+    won: !screen.values().any(|&tile| tile == Tile::Block)
+  })
+}
+
+fn get_arg(name: &str) -> Option<String> {
+  env::args().skip_while(|arg| arg != name).nth(1)
 }
 
 fn main() {
@@ -143,21 +323,67 @@ fn main() {
     .filter_map(|x| x.trim().parse::<i64>().ok())
     .collect::<Vec<_>>();
 
-  let (screen, _, _) = output_screen(&mut ProgramState::new(numbers.clone()), None);
-  let block_tile_count = screen.iter()
-    .filter(|&(_, &tile)| match tile {
-      Tile::Block => true,
-      _ => false
-    })
-    .count();
+  match output_screen(&mut Machine::new(numbers.clone()), None) {
+    Ok((screen, _, _)) => {
+      let block_tile_count = screen.iter()
+        .filter(|&(_, &tile)| tile == Tile::Block)
+        .count();
 
-  println!("Part 1: {}", block_tile_count);
+      println!("Part 1: {}", block_tile_count);
+    },
+    Err(err) => println!("Failure: {}", err)
+  }
 
   let mut program = numbers.clone();
   program[0] = 2;
 
-  let print_game = env::args().any(|s| s == "--print");
-  let score = play_game(&mut ProgramState::new(program), print_game);
+  let interactive = env::args().any(|s| s == "--play");
+  let print_game = interactive || env::args().any(|s| s == "--print");
+  let frame_duration = get_arg("--fps")
+    .and_then(|fps| fps.parse::<u64>().ok())
+    .filter(|&fps| fps > 0)
+    .map_or(time::Duration::from_millis(50), |fps| time::Duration::from_millis(1000 / fps));
+  let mut recording = get_arg("--record").map(|path| (path, String::new()));
+
+  let mut policy: Box<dyn Policy> = match (interactive, get_arg("--policy").as_deref()) {
+    (true, _) => Box::new(Keyboard::new()),
+    (_, Some("predict")) => Box::new(PredictTrajectory::default()),
+    _ => Box::new(FollowBall)
+  };
+
+  let result = play_game(&mut Machine::new(program.clone()), &mut *policy, |screen, score, frame| {
+    if print_game {
+      println!("{}", render_frame(screen, score, frame));
+      thread::sleep(frame_duration);
+    }
+
+    if let Some((_, text)) = &mut recording {
+      text.push_str(&render_frame(screen, score, frame));
+    }
+  });
 
-  println!("Part 2: {}", score);
+  match result {
+    Ok(GameResult { score, .. }) => println!("Part 2: {}", score),
+    Err(err) => println!("Failure: {}", err)
+  }
+
+  if let Some((path, text)) = recording {
+    if let Err(err) = fs::write(&path, text) {
+      println!("Failure: {}: {}", path, err);
+    }
+  }
+
+  if env::args().any(|s| s == "--compare") {
+    let policies: Vec<Box<dyn Policy>> = vec![Box::new(FollowBall), Box::new(PredictTrajectory::default())];
+
+    for mut policy in policies {
+      match play_game(&mut Machine::new(program.clone()), &mut *policy, |_, _, _| {}) {
+        Ok(GameResult { score, frames, paddle_moves, won }) => println!(
+          "{}: {} after {} frames, score {}, paddle moved in {} frames",
+          policy.name(), if won { "won" } else { "lost" }, frames, score, paddle_moves
+        ),
+        Err(err) => println!("{}: Failure: {}", policy.name(), err)
+      }
+    }
+  }
 }
@@ -39,7 +39,7 @@ fn parse_input(input: &str) -> RecipeBook<&str> {
   .filter_map(|(ingredients, outcome)| outcome.and_then(|(product, amount)| {
     let ingredients = ingredients.collect::<Recipe<_>>();
 
-    if ingredients.len() == 0 {
+    if ingredients.is_empty() {
       return None;
     }
 
@@ -73,7 +73,7 @@ where
     return 0;
   }
 
-  match recipes.get(&ingredient) {
+  match recipes.get(ingredient) {
     None => amount,
     Some((subrecipe, yield_amount)) => {
       let times = (amount as f64 / *yield_amount as f64).ceil() as u64;
@@ -35,7 +35,7 @@ fn do_phase(signal: &[u8], skipped: usize) -> Vec<u8> {
     (0..i - skipped)
     .map(|_| 0)
     .chain(
-      [1, 0, -1, 0].into_iter()
+      [1, 0, -1, 0].iter()
       .cloned()
       .flat_map(move |x| (0..=i).map(move |_| x))
       .cycle()
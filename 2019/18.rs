@@ -1,11 +1,13 @@
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 use std::collections::HashMap;
-use std::hash::Hash;
-use std::rc::Rc;
 
 mod graph;
+mod search;
+
 use graph::Graph;
+use search::Problem;
 
 #[derive(Debug, Copy, Clone)]
 enum Tile<K> {
@@ -16,36 +18,118 @@ enum Tile<K> {
 }
 
 type Position = (usize, usize);
-type PositionKeys<K> = (Position, Rc<Vec<K>>);
 type Labyrinth<K> = HashMap<Position, Tile<K>>;
+type KeySet = u32;
 
-impl<K: Hash + Eq + Clone + Ord> Graph<PositionKeys<K>> for Labyrinth<K> {
-  fn get_neighbors(&self, ((x, y), keys): PositionKeys<K>) -> Vec<PositionKeys<K>> {
-    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
+impl<K> Graph<Position> for Labyrinth<K> {
+  fn get_neighbors(&self, (x, y): Position) -> Vec<Position> {
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter()
     .cloned()
     .filter(|&(x, y)| x > 0 && y > 0)
-    .filter_map(|pos| match self.get(&pos) {
-      Some(Tile::Passage) => {
-        Some((pos, keys.clone()))
-      },
-      Some(Tile::Door(x)) if keys.contains(x) => {
-        Some((pos, keys.clone()))
-      },
-      Some(Tile::Key(k)) => {
-        Some((pos, {
-          let mut keys = (*keys).clone();
-
-          keys.push(k.clone());
-          keys.sort();
-          keys.dedup();
-
-          Rc::new(keys)
-        }))
-      },
-      _ => None
+    .filter(|pos| matches!(self.get(pos), Some(Tile::Passage) | Some(Tile::Door(_)) | Some(Tile::Key(_))))
+    .collect()
+  }
+}
+
+fn key_bit(key: char) -> KeySet {
+  1 << (key as u8 - b'a')
+}
+
+/// The shortest way from one point to a key, along with the doors on the
+/// way and the keys passed before reaching it.
+#[derive(Debug, Copy, Clone)]
+struct Route {
+  target: Position,
+  key: KeySet,
+  distance: usize,
+  doors: KeySet,
+  keys: KeySet
+}
+
+/// Robots collecting all keys of a vault. A state holds the robot positions
+/// and the keys collected so far.
+struct Vault {
+  all_keys: KeySet,
+  routes: HashMap<Position, Vec<Route>>
+}
+
+impl Vault {
+  fn new(labyrinth: &Labyrinth<char>, entrances: &[Position]) -> Vault {
+    let keys = labyrinth.iter()
+      .filter_map(|(&position, tile)| match tile {
+        Tile::Key(_) => Some(position),
+        _ => None
+      })
+      .collect::<Vec<_>>();
+
+    let routes = entrances.iter().chain(keys.iter())
+      .map(|&start| (start, Vault::get_routes(labyrinth, start)))
+      .collect();
+
+    Vault {
+      all_keys: keys.iter().fold(0, |all, position| match labyrinth[position] {
+        Tile::Key(k) => all | key_bit(k),
+        _ => all
+      }),
+      routes
+    }
+  }
+
+  fn get_routes(labyrinth: &Labyrinth<char>, start: Position) -> Vec<Route> {
+    // Distance, doors and keys strictly between the start and each position
+    let mut paths: HashMap<Position, (usize, KeySet, KeySet)> = HashMap::new();
+    let mut result = vec![];
+
+    for (previous, position) in labyrinth.bfs(start).predecessor() {
+      let (distance, mut doors, mut keys) = previous.map(|p| paths[&p]).unwrap_or((0, 0, 0));
+
+      if let Some(previous) = previous.filter(|&p| p != start) {
+        match labyrinth[&previous] {
+          Tile::Door(k) => doors |= key_bit(k),
+          Tile::Key(k) => keys |= key_bit(k),
+          _ => {}
+        }
+      }
+
+      let distance = if previous.is_some() { distance + 1 } else { 0 };
+
+      if let (Tile::Key(k), true) = (labyrinth[&position], position != start) {
+        result.push(Route { target: position, key: key_bit(k), distance, doors, keys });
+      }
+
+      paths.insert(position, (distance, doors, keys));
+    }
+
+    result
+  }
+}
+
+impl Problem for Vault {
+  type State = (Vec<Position>, KeySet);
+  type Key = (Vec<Position>, KeySet);
+
+  /// Moves one robot to a key it can reach without opening doors it has no
+  /// key for or passing keys it doesn't have yet.
+  fn successors(&self, (robots, keys): &Self::State) -> Vec<(usize, Self::State)> {
+    robots.iter().enumerate()
+    .flat_map(|(i, robot)| self.routes[robot].iter().map(move |route| (i, route)))
+    .filter(|(_, route)| route.key & keys == 0 && (route.doors | route.keys) & !keys == 0)
+    .map(|(i, route)| {
+      let mut robots = robots.clone();
+      robots[i] = route.target;
+
+      (route.distance, (robots, keys | route.key))
     })
     .collect()
   }
+
+  fn is_goal(&self, (_, keys): &Self::State) -> bool {
+    *keys == self.all_keys
+  }
+
+  fn canonical_key(&self, state: &Self::State) -> Option<Self::Key> {
+    Some(state.clone())
+  }
 }
 
 fn get_input() -> std::io::Result<String> {
@@ -86,96 +170,36 @@ fn parse_labyrinth(input: &str) -> (Labyrinth<char>, Option<Position>) {
   (labyrinth, entrance)
 }
 
-fn get_all_keys<K>(labyrinth: &Labyrinth<K>, position_key: PositionKeys<K>, key_count: usize) -> Option<Vec<Position>>
-where K: Hash + Eq + Clone + Ord {
-  let mut bfs_iter = labyrinth.bfs(position_key);
-
-  bfs_iter
-  .find(|(_, keys)| keys.len() == key_count)
-  .and_then(|target| {
-    bfs_iter.construct_path(target)
-    .map(|path| {
-      path.into_iter()
-      .map(|(position, _)| position)
-      .collect()
-    })
-  })
-}
+fn collect_keys(labyrinth: &Labyrinth<char>, entrances: Vec<Position>) -> Option<usize> {
+  let outcome = search::a_star(&Vault::new(labyrinth, &entrances), (entrances, 0));
 
-fn get_size<K>(labyrinth: &Labyrinth<K>) -> (usize, usize) {
-  let size = labyrinth.keys().cloned()
-    .fold((None, None), |(max_x, max_y), (x, y)| {
-      (
-        [max_x, Some(x)].into_iter().filter_map(|&x| x).max(),
-        [max_y, Some(y)].into_iter().filter_map(|&y| y).max()
-      )
-    });
-
-  match size {
-    (Some(x), Some(y)) => (x + 1, y + 1),
-    _ => (0, 0)
+  if env::args().any(|s| s == "--stats") {
+    println!("{}", outcome.statistics);
   }
-}
 
-fn real_get_all_keys<K>(
-  labyrinth: &Labyrinth<K>,
-  (x, y): Position,
-  key_count: usize
-) -> (Option<Vec<Position>>, Option<Vec<Position>>, Option<Vec<Position>>, Option<Vec<Position>>)
-where K: Hash + Eq + Clone + Ord {
-  let (width, height) = get_size(labyrinth);
-  let positions = vec![(x - 1, y - 1), (x + 1, y - 1), (x - 1, y + 1), (x + 1, y + 1)];
-
-  let keys = labyrinth.iter()
-    .filter_map(|(&position, tile)| match tile {
-      Tile::Key(k) => Some((position, k.clone())),
-      _ => None
-    })
-    .collect::<Vec<_>>();
-
-  let position_keys = positions.into_iter()
-    .enumerate()
-    .map(|(i, position)| {
-      let ignore_keys = keys.iter().cloned()
-        .filter(|&((x, y), _)| {
-          if i == 0 {
-            x > width / 2 || y > height / 2
-          } else if i == 1 {
-            x <= width / 2 || y > height / 2
-          } else if i == 2 {
-            x > width / 2 || y <= height / 2
-          } else if i == 3 {
-            x <= width / 2 || y <= height / 2
-          } else {
-            panic!()
-          }
-        })
-        .map(|(_, k)| k.clone())
-        .collect::<Vec<_>>();
-
-      (position, Rc::new(ignore_keys))
-    });
-
-  let mut results = position_keys
-    .filter_map(|position_key| get_all_keys(labyrinth, position_key, key_count));
-
-  (results.next(), results.next(), results.next(), results.next())
+  outcome.solution.map(|solution| solution.cost)
 }
 
 fn main() {
   let input = get_input().unwrap();
   let (mut labyrinth, position) = parse_labyrinth(&input);
+  let (x, y) = position.unwrap();
 
-  let path = get_all_keys(&labyrinth, (position.unwrap(), Rc::new(vec![])), 26);
-  println!("Part 1: {}", path.unwrap().len() - 1);
+  match collect_keys(&labyrinth, vec![(x, y)]) {
+    Some(steps) => println!("Part 1: {}", steps),
+    None => println!("Failure: not all keys can be collected")
+  }
 
-  let (x, y) = position.unwrap();
   labyrinth.insert((x, y), Tile::Wall);
   labyrinth.insert((x - 1, y), Tile::Wall);
   labyrinth.insert((x + 1, y), Tile::Wall);
   labyrinth.insert((x, y - 1), Tile::Wall);
   labyrinth.insert((x, y + 1), Tile::Wall);
 
-  let (p1, p2, p3, p4) = real_get_all_keys(&labyrinth, (x, y), 26);
-  println!("Part 2: {}", p1.unwrap().len() + p2.unwrap().len() + p3.unwrap().len() + p4.unwrap().len() - 4);
+  let entrances = vec![(x - 1, y - 1), (x + 1, y - 1), (x - 1, y + 1), (x + 1, y + 1)];
+
+  match collect_keys(&labyrinth, entrances) {
+    Some(steps) => println!("Part 2: {}", steps),
+    None => println!("Failure: not all keys can be collected")
+  }
 }
@@ -2,7 +2,6 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::collections::HashMap;
 
-mod intcode;
 use intcode::*;
 
 struct TractorBeamLevelIter<'a> {
@@ -31,7 +30,7 @@ impl<'a> Iterator for TractorBeamLevelIter<'a> {
 
     next_level.dedup();
 
-    if next_level.len() == 0 && self.previous_level.len() > 0 {
+    if next_level.is_empty() && !self.previous_level.is_empty() {
       let sum = self.previous_level.first().map(|(x, y)| x + y + 1).unwrap();
 
       next_level = (0..=sum).map(|x| (x, sum - x)).collect::<Vec<_>>();
@@ -51,8 +50,8 @@ fn get_input() -> std::io::Result<String> {
 }
 
 fn is_pulling(program: &[i64], (x, y): (u32, u32)) -> bool {
-  let mut state = ProgramState::new(program.to_vec());
-  let (result, _) = run_program_with_inputs(&mut state, vec![x, y].into_iter().map(|x| x as i64));
+  let mut state = Machine::new(program.to_vec());
+  let (result, _) = run_program_with_inputs(&mut state, vec![x, y].into_iter().map(|x| x as i64)).unwrap();
 
   match result.last() {
     Some(&x) => x == 1,
@@ -95,7 +94,7 @@ fn main() {
       && is_pulling_with_cache(&program, (x, y + 99), &mut pull_cache)
     });
 
-  square_fit_position.map(|(x, y)| {
+  if let Some((x, y)) = square_fit_position {
     println!("Part 2: {}", x * 10000 + y);
-  });
+  }
 }
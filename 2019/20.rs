@@ -4,7 +4,7 @@ use std::hash::Hash;
 use std::collections::HashMap;
 
 mod graph;
-use graph::Graph;
+use graph::{Graph, WeightedGraph};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum PortalType {
@@ -37,11 +37,7 @@ impl<P: Hash + Eq + Clone> Graph<Position> for Labyrinth<Position, P> {
     };
 
     let mut result = vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
-      .filter(|&(x, y)| x >= 0 && y >= 0)
-      .filter(|pos| match self.tiles.get(pos) {
-        Some(Tile::Passage) | Some(Tile::Portal(_, _)) => true,
-        _ => false
-      })
+      .filter(|pos| matches!(self.tiles.get(pos), Some(Tile::Passage) | Some(Tile::Portal(_, _))))
       .collect::<Vec<_>>();
 
     if let Some(p) = portal {
@@ -61,16 +57,12 @@ impl<P: Hash + Eq + Clone> Graph<Position> for Labyrinth<Position, P> {
 impl<P: Hash + Eq + Clone> Graph<(Position, usize)> for Labyrinth<Position, P> {
   fn get_neighbors(&self, ((x, y), level): (Position, usize)) -> Vec<(Position, usize)> {
     let (portal, portal_type) = match self.tiles.get(&(x, y)) {
-      Some(Tile::Portal(p, t)) => (Some(p.clone()), Some(t.clone())),
+      Some(Tile::Portal(p, t)) => (Some(p.clone()), Some(*t)),
       _ => (None, None)
     };
 
     let mut result = vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
-      .filter(|&(x, y)| x >= 0 && y >= 0)
-      .filter(|pos| match self.tiles.get(pos) {
-        Some(Tile::Passage) | Some(Tile::Portal(_, _)) => true,
-        _ => false
-      })
+      .filter(|pos| matches!(self.tiles.get(pos), Some(Tile::Passage) | Some(Tile::Portal(_, _))))
       .map(|pos| (pos, level))
       .collect::<Vec<_>>();
 
@@ -123,13 +115,13 @@ fn parse_labyrinth(input: &str) -> Labyrinth<Position, String> {
           (x, y + d)      // Bottom
         );
 
-        let mut label_char_iter = [1, 2].into_iter().cloned()
+        let mut label_char_iter = [1, 2].iter().cloned()
           .map(|d| get_label_positions((x as isize, y as isize), d));
 
         let (portal_type, mut label) = match (label_char_iter.next(), label_char_iter.next()) {
           (Some((l11, l21, l31, l41)), Some((l12, l22, l32, l42))) => {
             [(l11, l12), (l21, l22), (l31, l32), (l41, l42)]
-            .into_iter()
+            .iter()
             .cloned()
             .map(|((a, b), (c, d))| ((a as usize, b as usize), (c as usize, d as usize)))
             .enumerate()
@@ -143,14 +135,13 @@ fn parse_labyrinth(input: &str) -> Labyrinth<Position, String> {
               label.push(c1);
               label.push(c2);
 
-              let portal_type = match [
+              let (a, b, x, y) = [
                 (x, width, PortalType::Outer, PortalType::Inner),  // Left
                 (x, width, PortalType::Inner, PortalType::Outer),  // Right
                 (y, height, PortalType::Outer, PortalType::Inner), // Top
                 (y, height, PortalType::Inner, PortalType::Outer)  // Bottom
-              ][i] {
-                (a, b, x, y) => if a < b / 2 { x } else { y }
-              };
+              ][i];
+              let portal_type = if a < b / 2 { x } else { y };
 
               (Some(portal_type), Some(label))
             })
@@ -210,8 +201,13 @@ fn main() {
   let entrance = labyrinth.entrance.unwrap();
   let goal = labyrinth.goal.unwrap();
 
-  let path = labyrinth.bfs(entrance).construct_path(goal).unwrap();
-  println!("Part 1: {}", path.len() - 1);
+  // Only portals are interesting, so walk between them on a contracted graph
+  let points = labyrinth.portals.values()
+    .flat_map(|&(pos1, pos2)| vec![pos1, pos2])
+    .chain(vec![entrance, goal])
+    .collect::<Vec<_>>();
+  let (steps, _) = labyrinth.contract(&points).dijkstra(entrance).construct_path(goal).unwrap();
+  println!("Part 1: {}", steps);
 
   let path = labyrinth.bfs((entrance, 0)).construct_path((goal, 0)).unwrap();
   println!("Part 2: {}", path.len() - 1);
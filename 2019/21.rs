@@ -2,8 +2,13 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::iter;
 
-mod intcode;
+mod springscript;
+
 use intcode::*;
+use springscript::{parse_hull, synthesize, Mode, Script, MAX_INSTRUCTIONS};
+
+/// Number of distinct register states the synthesizer may visit.
+const SEARCH_LIMIT: usize = 1_000_000;
 
 #[derive(Debug, Clone)]
 enum DroidResult {
@@ -19,17 +24,17 @@ fn get_input() -> std::io::Result<String> {
   Ok(contents)
 }
 
-fn run_springdroid(state: &mut ProgramState, script: &str) -> DroidResult {
+fn run_springdroid(state: &mut Machine, script: &str) -> DroidResult {
   let inputs = script.lines()
     .map(|line| line.trim())
-    .filter(|line| line.len() > 0 && line.chars().next().unwrap() != '#')
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
     .flat_map(|instruction| instruction.chars().chain(iter::once('\n')))
     .map(|c| c as i64);
 
-  let (outputs, _) = run_program_with_inputs(state, inputs);
+  let (outputs, _) = run_program_with_inputs(state, inputs).unwrap();
 
   match outputs.last() {
-    Some(&x) if x > u8::max_value() as i64 => {
+    Some(&x) if x > u8::MAX as i64 => {
       DroidResult::Success(x)
     },
     _ => {
@@ -45,31 +50,29 @@ fn run_springdroid(state: &mut ProgramState, script: &str) -> DroidResult {
   }
 }
 
-fn main() {
-  let input = get_input().unwrap();
-  let program = input.split(',')
-    .filter_map(|x| x.trim().parse::<i64>().ok())
-    .collect::<Vec<_>>();
+/// Synthesizes scripts until one gets the droid across. Every hull the
+/// droid falls into is recorded and has to be crossed by the next script.
+fn find_script(program: &[i64], mode: Mode) -> Result<(Script, i64), String> {
+  let mut hulls = vec![];
 
-  let springscript = "
-    # There's a hole in ABC
-    NOT A J
-    NOT J J
-    AND B J
-    AND C J
-    NOT J J
-
-    # and D is not a hole
-    AND D J
+  loop {
+    let script = synthesize(&hulls, mode, MAX_INSTRUCTIONS, SEARCH_LIMIT)
+      .ok_or_else(|| format!("no script crosses all {} recorded hulls", hulls.len()))?;
 
-    WALK
-  ";
+    match run_springdroid(&mut Machine::new(program.to_vec()), &script.to_string()) {
+      DroidResult::Success(x) => return Ok((script, x)),
+      DroidResult::Fail(msg) => hulls.push(parse_hull(&msg).ok_or(msg)?)
+    }
+  }
+}
 
-  let result = run_springdroid(&mut ProgramState::new(program.clone()), springscript);
+fn main() {
+  let input = get_input().unwrap();
+  let program = parse_program(&input);
 
-  match result {
-    DroidResult::Success(x) => println!("Part 1: {}", x),
-    DroidResult::Fail(msg) => println!("Failure: {}", msg)
+  match find_script(&program, Mode::Walk) {
+    Ok((_, x)) => println!("Part 1: {}", x),
+    Err(msg) => println!("Failure: {}", msg)
   }
 
   let springscript = "
@@ -105,10 +108,12 @@ fn main() {
     RUN
   ";
 
-  let result = run_springdroid(&mut ProgramState::new(program.clone()), springscript);
+  let result = Script::parse(springscript)
+    .map_err(|err| err.to_string())
+    .map(|script| run_springdroid(&mut Machine::new(program.clone()), &script.to_string()));
 
   match result {
-    DroidResult::Success(x) => println!("Part 2: {}", x),
-    DroidResult::Fail(msg) => println!("Failure: {}", msg)
+    Ok(DroidResult::Success(x)) => println!("Part 2: {}", x),
+    Ok(DroidResult::Fail(msg)) | Err(msg) => println!("Failure: {}", msg)
   }
 }
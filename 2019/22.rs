@@ -23,10 +23,10 @@ fn parse_instructions(input: &str) -> Vec<ShuffleOperation> {
       Some(ShuffleOperation::DealIntoNewStack)
     } else if &line[.."cut".len()] == "cut" {
       line["cut".len()..].trim().parse::<isize>().ok()
-      .map(|n| ShuffleOperation::CutNCards(n))
+      .map(ShuffleOperation::CutNCards)
     } else if &line[.."deal with increment".len()] == "deal with increment" {
       line["deal with increment".len()..].trim().parse::<usize>().ok()
-      .map(|n| ShuffleOperation::DealWithIncrement(n))
+      .map(ShuffleOperation::DealWithIncrement)
     } else {
       None
     }
@@ -27,7 +27,7 @@ impl<P: Hash + Eq + Copy> TileMap<P> {
 
     let considered_positions = self.0.iter()
       .flat_map(|(&pos, _)| self.get_neighbors(pos))
-      .filter(|pos| !self.0.contains_key(&pos))
+      .filter(|pos| !self.0.contains_key(pos))
       .chain(self.0.keys().cloned());
 
     for position in considered_positions {
@@ -64,8 +64,8 @@ impl TileMap<Position> {
     let size = self.0.keys().cloned()
       .fold((None, None), |(max_x, max_y), (x, y)| {
         (
-          [max_x, Some(x)].into_iter().filter_map(|&x| x).max(),
-          [max_y, Some(y)].into_iter().filter_map(|&y| y).max()
+          [max_x, Some(x)].iter().filter_map(|&x| x).max(),
+          [max_y, Some(y)].iter().filter_map(|&y| y).max()
         )
       });
 
@@ -99,10 +99,7 @@ impl TileMap<Position> {
     (0..height)
     .flat_map(|y| (0..width).map(move |x| (x, y)))
     .enumerate()
-    .filter(|&(_, pos)| match self.0.get(&pos) {
-      Some(&Tile::Bug) => true,
-      _ => false
-    })
+    .filter(|&(_, pos)| matches!(self.0.get(&pos), Some(&Tile::Bug)))
     .map(|(i, _)| 2u64.pow(i as u32))
     .sum::<u64>()
   }
@@ -116,7 +113,7 @@ impl Graph<Position> for TileMap<Position> {
 
     let (x, y) = (x as isize, y as isize);
 
-    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter().cloned()
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter().cloned()
     .filter(|&(x, y)| x >= 0 && y >= 0)
     .map(|(x, y)| (x as usize, y as usize))
     .filter(|pos| self.0.contains_key(pos))
@@ -140,7 +137,7 @@ impl Graph<RecursivePosition> for TileMap<RecursivePosition> {
     let (level, (x, y)) = position;
     let (x, y) = (x as isize, y as isize);
 
-    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter().cloned()
+    [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter().cloned()
     .map(|pos| (level, pos))
     .flat_map(|(level, (nx, ny))| {
       if (nx, ny) == (2, 2) {
@@ -1,69 +1,54 @@
 use std::env;
+use std::error::Error;
 use std::fs::File;
-use std::iter;
 use std::io;
 use std::io::prelude::*;
-use std::collections::HashMap;
 
-mod intcode;
+mod explore;
 mod graph;
 
 use intcode::*;
+use explore::{Agent, Explorer, Outcome};
 use graph::Graph;
 
-#[derive(Debug)]
-struct ShipMap {
-  rooms: Vec<String>,
-  passages: HashMap<usize, HashMap<usize, String>>,
-  items: HashMap<usize, Vec<String>>
+/// Number of instructions a single command may take before the droid is
+/// considered stuck in an infinite loop.
+const STEP_LIMIT: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Room {
+  name: String,
+  doors: Vec<String>,
+  items: Vec<String>
 }
 
-impl ShipMap {
-  fn new(rooms: &[&str]) -> ShipMap {
-    ShipMap {
-      rooms: rooms.iter().map(|&x| x.to_owned()).collect(),
-      passages: HashMap::new(),
-      items: HashMap::new()
-    }
-  }
+#[derive(Debug)]
+enum Response {
+  Output(String),
+  Halt(String),
+  Stuck
+}
 
-  fn get_room_id(&self, room: &str) -> Option<usize> {
-    self.rooms.iter().position(|r| r == room)
-  }
+/// The droid as an exploring agent, going through doors.
+struct Droid {
+  state: Machine
+}
 
-  fn insert_passage(&mut self, room1: &str, direction: &str, room2: &str) {
-    let id1 = self.get_room_id(room1);
-    let id2 = self.get_room_id(room2);
-
-    let opposite = match direction {
-      "north" => "south",
-      "west" => "east",
-      "south" => "north",
-      "east" => "west",
-      _ => return
-    };
-
-    if let (Some(id1), Some(id2)) = (id1, id2) {
-      if let Some(value) = self.passages.get_mut(&id1) {
-        value.insert(id2, direction.to_owned());
-      } else {
-        self.passages.insert(id1, iter::once((id2, direction.to_owned())).collect());
-      }
+impl Agent for Droid {
+  type Position = String;
+  type Move = String;
+  type Tile = Room;
+  type Error = VmError;
 
-      if let Some(value) = self.passages.get_mut(&id2) {
-        value.insert(id1, opposite.to_owned());
-      } else {
-        self.passages.insert(id2, iter::once((id1, opposite.to_owned())).collect());
-      }
-    }
+  fn moves(&self, _: &String, room: &Room) -> Vec<String> {
+    room.doors.clone()
   }
-}
 
-impl Graph<usize> for ShipMap {
-  fn get_neighbors(&self, room_id: usize) -> Vec<usize> {
-    self.passages.get(&room_id)
-    .map(|passages| passages.keys().cloned().collect::<Vec<_>>())
-    .unwrap_or_else(|| vec![])
+  fn step(&mut self, room: &String, door: &String) -> Result<Outcome<String, Room>, VmError> {
+    Ok(match try_door(&mut self.state, room, door)? {
+      Some(next_room) => Outcome::Moved(next_room.name.clone(), next_room),
+      None => Outcome::Blocked(None)
+    })
   }
 }
 
@@ -75,193 +60,174 @@ fn get_input() -> std::io::Result<String> {
   Ok(contents)
 }
 
-fn get_ship() -> ShipMap {
-  // Returns premapped ship
-
-  let mut ship = ShipMap::new(&[
-    "Hull Breach",
-    "Engineering",
-    "Hallway",
-    "Observatory",
-    "Storage",
-    "Security",
-    "Gift Wrapping",
-    "Arcade",
-    "Crew",
-    "Kitchen",
-    "Passages",
-    "Navigation",
-    "Corridor",
-    "Stables",
-    "Sick Bay",
-    "Warp Drive",
-    "Science Lab"
-  ]);
-
-  ship.items = [
-    ("Storage", vec!["space heater"]),
-    ("Gift Wrapping", vec!["antenna"]),
-    ("Crew", vec!["fixed point"]),
-    ("Kitchen", vec!["asterisk"]),
-    ("Passages", vec!["festive hat"]),
-    ("Corridor", vec!["jam"]),
-    ("Stables", vec!["easter egg"]),
-    ("Sick Bay", vec!["tambourine"])
-  ].into_iter()
-    .filter_map(|(room, items)| (
-      ship.get_room_id(room)
-      .map(|id| (id, items.iter().map(|&x| x.to_owned()).collect::<Vec<_>>()))
-    ))
-    .collect();
-
-  ship.insert_passage("Hull Breach", "south", "Engineering");
-  ship.insert_passage("Arcade", "north", "Engineering");
-  ship.insert_passage("Arcade", "south", "Crew");
-  ship.insert_passage("Arcade", "west", "Science Lab");
-  ship.insert_passage("Warp Drive", "south", "Science Lab");
-  ship.insert_passage("Warp Drive", "north", "Sick Bay");
-  ship.insert_passage("Kitchen", "east", "Crew");
-  ship.insert_passage("Passages", "north", "Crew");
-  ship.insert_passage("Passages", "west", "Navigation");
-  ship.insert_passage("Corridor", "east", "Navigation");
-  ship.insert_passage("Corridor", "south", "Stables");
-  ship.insert_passage("Hallway", "east", "Engineering");
-  ship.insert_passage("Hallway", "south", "Gift Wrapping");
-  ship.insert_passage("Hallway", "west", "Observatory");
-  ship.insert_passage("Storage", "east", "Observatory");
-  ship.insert_passage("Storage", "west", "Security");
-
-  ship
+fn parse_list(lines: &[&str], header: &str) -> Vec<String> {
+  lines.iter()
+    .skip_while(|&&line| line != header)
+    .skip(1)
+    .take_while(|line| line.starts_with("- "))
+    .map(|line| line[2..].to_owned())
+    .collect()
 }
 
-fn move_droid(state: &mut ProgramState, ship: &ShipMap, room: &mut String, target: &str) -> bool {
-  let id = ship.get_room_id(room);
-  let target_id = ship.get_room_id(target);
-
-  if let (Some(mut id), Some(target_id)) = (id, target_id) {
-    let path = ship.bfs(id).construct_path(target_id).unwrap_or_else(|| vec![]);
-
-    for next_room_id in path.into_iter().skip(1) {
-      let direction = ship.passages.get(&id)
-        .and_then(|neighbored_rooms| neighbored_rooms.get(&next_room_id));
-
-      if let Some(direction) = direction {
-        let input = direction.to_owned() + "\n";
-        let (output, _) = run_ascii_program_with_input(state, &input);
+/// Parses the last room description in `output`, i.e. the room the droid
+/// ends up in.
+fn parse_room(output: &str) -> Option<Room> {
+  let start = output.rfind("== ")?;
+  let lines = output[start..].lines().collect::<Vec<_>>();
+  let name = lines[0].trim_start_matches("== ").trim_end_matches(" ==");
+
+  Some(Room {
+    name: name.to_owned(),
+    doors: parse_list(&lines, "Doors here lead:"),
+    items: parse_list(&lines, "Items here:")
+  })
+}
 
-        id = next_room_id;
-        *room = ship.rooms[next_room_id].clone();
-      } else {
-        break;
-      }
+fn send_command(state: &mut Machine, command: &str) -> Result<Response, VmError> {
+  let mut input = command.chars()
+    .chain(if command.is_empty() { None } else { Some('\n') })
+    .map(|c| c as i64);
+  let mut output = String::new();
+  let mut value = None;
+
+  for _ in 0..STEP_LIMIT {
+    match state.step(&mut value)? {
+      None => {},
+      Some(ProgramResult::Output(x)) => output.push(x as u8 as char),
+      Some(ProgramResult::WaitForInput) => match input.next() {
+        Some(x) => value = Some(x),
+        None => return Ok(Response::Output(output))
+      },
+      Some(ProgramResult::Halt) => return Ok(Response::Halt(output))
     }
   }
 
-  target == room
+  Ok(Response::Stuck)
 }
 
-fn get_subsets<T: Clone>(set: &[T]) -> Vec<Vec<T>> {
-  set.first()
-  .map(|first| {
-    get_subsets(&set[1..]).into_iter()
-    .chain(
-      get_subsets(&set[1..]).into_iter()
-      .map(|mut subset| {
-        subset.push(first.clone());
-        subset
-      })
-    )
-    .collect()
+/// Moves through `door` and returns the room the droid ends up in, if it
+/// actually left `room`.
+fn try_door(state: &mut Machine, room: &str, door: &str) -> Result<Option<Room>, VmError> {
+  Ok(match send_command(state, door)? {
+    Response::Output(output) => parse_room(&output).filter(|next| next.name != room),
+    _ => None
   })
-  .unwrap_or_else(|| vec![vec![]])
 }
 
-fn main() {
-  let input = get_input().unwrap();
-  let program = input.split(',')
-    .filter_map(|x| x.trim().parse::<i64>().ok())
-    .collect::<Vec<_>>();
+/// Items are deadly if taking them halts the program, hangs it, or keeps
+/// the droid from moving on.
+fn is_safe_item(state: &Machine, room: &Room, door: &str, item: &str) -> Result<bool, VmError> {
+  let mut state = state.fork();
 
-  let mut state = ProgramState::new(program.clone());
+  match send_command(&mut state, &format!("take {}", item))? {
+    Response::Output(_) => Ok(try_door(&mut state, &room.name, door)?.is_some()),
+    _ => Ok(false)
+  }
+}
 
-  if env::args().any(|s| s == "--print") {
-    let stdin = io::stdin();
+/// Tries item combinations on the pressure-sensitive floor, fewest items
+/// first. Combinations contained in a too light one or containing a too
+/// heavy one are skipped.
+fn pass_checkpoint(state: &Machine, items: &[String], door: &str) -> Result<Option<String>, VmError> {
+  let mut masks = (0..1u32 << items.len()).collect::<Vec<_>>();
+  let mut too_light: Vec<u32> = vec![];
+  let mut too_heavy: Vec<u32> = vec![];
+
+  masks.sort_by_key(|mask| mask.count_ones());
+
+  for mask in masks {
+    if too_light.iter().any(|&light| mask & !light == 0)
+      || too_heavy.iter().any(|&heavy| heavy & !mask == 0)
+    {
+      continue;
+    }
 
-    for line in iter::once(Ok("".to_owned())).chain(stdin.lock().lines()) {
-      let mut line = line.unwrap();
+    let mut state = state.fork();
 
-      if line.len() > 0 {
-        line.push('\n');
+    for (i, item) in items.iter().enumerate() {
+      if mask & (1 << i) == 0 {
+        send_command(&mut state, &format!("drop {}", item))?;
       }
+    }
 
-      let (output, result) = run_ascii_program_with_input(&mut state, &line);
-      print!("{}", output);
-
-      if let ProgramResult::Halt = result {
-        break;
-      }
+    match send_command(&mut state, door)? {
+      Response::Halt(output) => return Ok(Some(output)),
+      Response::Output(output) if output.contains("heavier") => too_light.push(mask),
+      Response::Output(output) if output.contains("lighter") => too_heavy.push(mask),
+      _ => {}
     }
   }
 
-  let ship = get_ship();
-
-  // First, collect all collectable items
-
-  let mut all_items = vec![];
-  let mut room = "Hull Breach".to_owned();
-  let dfs_iter = ship.dfs(ship.get_room_id(&room).unwrap());
+  Ok(None)
+}
 
-  run_ascii_program_with_input(&mut state, "");
+fn solve(program: &[i64]) -> Result<String, Box<dyn Error>> {
+  let mut state = Machine::new(program.to_vec());
+  let room = match send_command(&mut state, "")? {
+    Response::Output(output) => parse_room(&output),
+    _ => None
+  }.ok_or("droid did not start in a room")?;
 
-  for next_room_id in dfs_iter {
-    let next_room = &ship.rooms[next_room_id];
-    move_droid(&mut state, &ship, &mut room, next_room);
+  // Map the ship. The pressure-sensitive floor throws the droid back, so
+  // its door is the only blocked one.
 
-    let items = ship.items.get(&next_room_id);
+  let start = room.name.clone();
+  let mut explorer = Explorer::new(Droid { state }, start.clone(), room);
 
-    if let Some(items) = items {
-      for item in items {
-        let input = "take ".to_owned() + item + "\n";
-        run_ascii_program_with_input(&mut state, &input);
-        all_items.push(item.clone());
-      }
-    }
-  }
+  explorer.explore(|_| {})?;
 
-  // Move to Security
+  let (checkpoint, door) = explorer.blocked.first()
+    .cloned()
+    .ok_or("no pressure-sensitive floor found")?;
 
-  move_droid(&mut state, &ship, &mut room, "Security");
+  // Collect all safe items
 
-  // Try all combinations of items
+  let rooms = explorer.dfs(start)
+    .filter(|room| !explorer.tiles[room].items.is_empty())
+    .collect::<Vec<_>>();
+  let mut all_items = vec![];
 
-  let subsets = get_subsets(&all_items);
+  for room in rooms {
+    explorer.walk_to(room.clone())?;
 
-  for subset in &subsets {
-    // Drop items in the subset
+    let room = &explorer.tiles[&room];
+    let exit = explorer.passages[&room.name].values().next()
+      .ok_or_else(|| format!("{} has no way out", room.name))?;
 
-    for item in subset {
-      let input = "drop ".to_owned() + item + "\n";
-      run_ascii_program_with_input(&mut state, &input);
+    for item in &room.items {
+      if is_safe_item(&explorer.agent.state, room, exit, item)? {
+        send_command(&mut explorer.agent.state, &format!("take {}", item))?;
+        all_items.push(item.clone());
+      }
     }
+  }
 
-    // Try to get past security
+  explorer.walk_to(checkpoint)?;
 
-    let (output, _) = run_ascii_program_with_input(&mut state, "west\n");
+  let output = pass_checkpoint(&explorer.agent.state, &all_items, &door)?
+    .ok_or("no combination of items passes the checkpoint")?;
 
-    if output.contains("hello") {
-      let line = output.lines()
-        .find(|line| line.contains("hello"))
-        .unwrap();
+  output.lines()
+    .find(|line| line.contains("keypad"))
+    .map(|line| line.to_owned())
+    .ok_or_else(|| "no password found".into())
+}
 
-      println!("Part 1: {}", line);
-      break;
-    }
+fn main() {
+  let input = get_input().unwrap();
+  let program = parse_program(&input);
 
-    // Retake dropped items
+  if env::args().any(|s| s == "--print") {
+    let stdin = io::stdin();
+    let mut terminal = Terminal::new(Machine::new(program.clone()));
 
-    for item in subset {
-      let input = "take ".to_owned() + item + "\n";
-      run_ascii_program_with_input(&mut state, &input);
+    if let Err(err) = terminal.run(stdin.lock(), io::stdout()) {
+      println!("Failure: {}", err);
     }
   }
+
+  match solve(&program) {
+    Ok(line) => println!("Part 1: {}", line),
+    Err(err) => println!("Failure: {}", err)
+  }
 }
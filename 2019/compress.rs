@@ -0,0 +1,185 @@
+#![allow(dead_code)]
+
+/// Subroutines are named by the letters `A` to `Z`, so there can't be more.
+pub const MAX_ROUTINES: usize = 26;
+
+/// Limits of a compression. Lengths are in encoded characters, counting the
+/// commas between tokens, and each call in the main routine takes one
+/// character like `A`.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+  pub routines: usize,
+  pub main_length: usize,
+  pub routine_length: usize
+}
+
+/// A main routine calling subroutines by index. Subroutines are numbered in
+/// order of their first call.
+#[derive(Debug, PartialEq)]
+pub struct Compression<'a, T> {
+  pub main: Vec<usize>,
+  pub routines: Vec<&'a [T]>
+}
+
+impl<'a, T> Clone for Compression<'a, T> {
+  fn clone(&self) -> Self {
+    Compression { main: self.main.clone(), routines: self.routines.clone() }
+  }
+}
+
+impl<'a, T> Compression<'a, T> {
+  pub fn decompress(&self) -> Vec<&'a T> {
+    self.main.iter()
+      .flat_map(|&i| self.routines[i].iter())
+      .collect()
+  }
+
+  /// Encoded length of the main routine and all subroutines together.
+  pub fn encoded_len<F: Fn(&T) -> usize>(&self, token_len: F) -> usize {
+    self.main.len() * 2 - 1 + self.routines.iter()
+      .map(|routine| encoded_len(routine, &token_len))
+      .sum::<usize>()
+  }
+
+  /// Encodes the main routine and the subroutines as separate lines,
+  /// naming subroutines `A`, `B`, `C` and so on.
+  pub fn encode<F: Fn(&T) -> String>(&self, encode_token: F) -> Vec<String> {
+    let main = self.main.iter()
+      .map(|&i| ((b'A' + i as u8) as char).to_string())
+      .collect::<Vec<_>>()
+      .join(",");
+
+    Some(main).into_iter()
+      .chain(self.routines.iter().map(|routine| {
+        routine.iter().map(&encode_token).collect::<Vec<_>>().join(",")
+      }))
+      .collect()
+  }
+}
+
+pub fn encoded_len<T, F: Fn(&T) -> usize>(tokens: &[T], token_len: F) -> usize {
+  tokens.iter().map(token_len).sum::<usize>() + tokens.len().saturating_sub(1)
+}
+
+fn search<'a, T: Eq, F: Fn(&T) -> usize>(
+  tokens: &'a [T],
+  position: usize,
+  limits: &Limits,
+  token_len: &F,
+  current: &mut Compression<'a, T>,
+  solutions: &mut Vec<Compression<'a, T>>
+) {
+  if position == tokens.len() {
+    solutions.push(current.clone());
+    return;
+  }
+
+  if current.main.len() * 2 + 1 > limits.main_length {
+    return;
+  }
+
+  let rest = &tokens[position..];
+
+  for (i, routine) in current.routines.clone().into_iter().enumerate() {
+    if rest.starts_with(routine) {
+      current.main.push(i);
+      search(tokens, position + routine.len(), limits, token_len, current, solutions);
+      current.main.pop();
+    }
+  }
+
+  if current.routines.len() >= limits.routines.min(MAX_ROUTINES) {
+    return;
+  }
+
+  for len in 1..=rest.len() {
+    let routine = &rest[..len];
+
+    if encoded_len(routine, token_len) > limits.routine_length {
+      break;
+    } else if current.routines.contains(&routine) {
+      continue;
+    }
+
+    current.main.push(current.routines.len());
+    current.routines.push(routine);
+    search(tokens, position + len, limits, token_len, current, solutions);
+    current.routines.pop();
+    current.main.pop();
+  }
+}
+
+/// Finds every way to write `tokens` as a main routine calling at most
+/// `limits.routines` subroutines within the length limits. Limits above
+/// `MAX_ROUTINES` subroutines are lowered to it.
+pub fn compress_all<'a, T: Eq, F: Fn(&T) -> usize>(tokens: &'a [T], limits: &Limits, token_len: F) -> Vec<Compression<'a, T>> {
+  let mut solutions = vec![];
+  let mut current = Compression { main: vec![], routines: vec![] };
+
+  if !tokens.is_empty() {
+    search(tokens, 0, limits, &token_len, &mut current, &mut solutions);
+  }
+
+  solutions
+}
+
+/// Finds the compression with the shortest encoding.
+pub fn compress<'a, T: Eq, F: Fn(&T) -> usize>(tokens: &'a [T], limits: &Limits, token_len: F) -> Option<Compression<'a, T>> {
+  compress_all(tokens, limits, &token_len).into_iter()
+    .min_by_key(|compression| compression.encoded_len(&token_len))
+}
+
+#[test]
+fn scaffold_example() {
+  let tokens = "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+    .split(',')
+    .collect::<Vec<_>>();
+  let limits = Limits { routines: 3, main_length: 20, routine_length: 20 };
+  let solutions = compress_all(&tokens, &limits, |token| token.len());
+
+  assert!(solutions.iter().all(|solution| solution.decompress().into_iter().eq(tokens.iter())));
+  assert!(solutions.iter().any(|solution| solution.encode(|token| token.to_string()) == vec![
+    "A,B,C,B,A,C", "R,8,R,8", "R,4,R,4,R,8", "L,6,L,2"
+  ]));
+
+  let best = compress(&tokens, &limits, |token| token.len()).unwrap();
+  assert!(best.encoded_len(|token| token.len()) <= 11 + 7 + 11 + 7);
+}
+
+#[test]
+fn greedy_chunking_fails() {
+  // Starting with the longest possible routine `a,a` leaves `a,b,b`, which
+  // can't be covered with one more routine
+  let tokens = vec!['a', 'a', 'a', 'b', 'b'];
+  let limits = Limits { routines: 2, main_length: 7, routine_length: 3 };
+  let solutions = compress_all(&tokens, &limits, |_| 1);
+
+  assert_eq!(solutions, vec![Compression { main: vec![0, 0, 0, 1], routines: vec![&tokens[..1], &tokens[3..]] }]);
+}
+
+#[test]
+fn counts_separators() {
+  let tokens = vec![10, 10, 10, 10];
+  let token_len = |x: &i32| x.to_string().len();
+
+  let limits = Limits { routines: 1, main_length: 3, routine_length: 5 };
+  assert_eq!(compress(&tokens, &limits, token_len).unwrap().routines, vec![&tokens[..2]]);
+
+  let limits = Limits { routines: 1, main_length: 3, routine_length: 4 };
+  assert_eq!(compress(&tokens, &limits, token_len), None);
+}
+
+#[test]
+fn routine_names() {
+  let tokens = (0..27).collect::<Vec<i32>>();
+  let token_len = |x: &i32| x.to_string().len();
+  let limits = Limits { routines: 100, main_length: 100, routine_length: 2 };
+
+  assert_eq!(compress_all(&tokens, &limits, token_len), vec![]);
+
+  let solutions = compress_all(&tokens[..26], &limits, token_len);
+  let main = solutions[0].encode(|x| x.to_string()).remove(0);
+
+  assert_eq!(solutions.len(), 1);
+  assert_eq!(main, ('A'..='Z').map(String::from).collect::<Vec<_>>().join(","));
+}
@@ -0,0 +1,269 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use crate::graph::{Graph, Vertex};
+
+pub type GridPosition = (isize, isize);
+
+/// Unit steps north, south, west and east, in the order Intcode droids
+/// number their movement commands.
+pub const DIRECTIONS: [GridPosition; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+pub enum Outcome<P, T> {
+  Moved(P, T),
+  /// The agent stayed where it was. Blocking tiles like walls can be
+  /// revealed along with their position.
+  Blocked(Option<(P, T)>)
+}
+
+/// A black box moving through an unknown world, e.g. an Intcode droid.
+pub trait Agent {
+  type Position: Vertex;
+  type Move: Clone;
+  type Tile;
+  type Error;
+
+  /// Moves worth trying on `tile`.
+  fn moves(&self, position: &Self::Position, tile: &Self::Tile) -> Vec<Self::Move>;
+
+  /// Where a move leads, if it's known in advance like on grids. Moves to
+  /// known positions don't have to be tried.
+  fn target(&self, _position: &Self::Position, _step: &Self::Move) -> Option<Self::Position> {
+    None
+  }
+
+  fn step(&mut self, position: &Self::Position, step: &Self::Move) -> Result<Outcome<Self::Position, Self::Tile>, Self::Error>;
+}
+
+/// Maps the world of an agent by trying every move once. The agent always
+/// heads for the nearest position with untried moves, walking there on
+/// known passages.
+pub struct Explorer<A: Agent> {
+  pub agent: A,
+  pub position: A::Position,
+  pub tiles: HashMap<A::Position, A::Tile>,
+  pub passages: HashMap<A::Position, HashMap<A::Position, A::Move>>,
+  /// Moves that left the agent where it was.
+  pub blocked: Vec<(A::Position, A::Move)>,
+  untried: HashMap<A::Position, Vec<A::Move>>
+}
+
+impl<A: Agent> Graph<A::Position> for Explorer<A> {
+  fn get_neighbors(&self, position: A::Position) -> Vec<A::Position> {
+    self.passages.get(&position)
+    .map(|passages| passages.keys().cloned().collect::<Vec<_>>())
+    .unwrap_or_default()
+  }
+}
+
+impl<A: Agent> Explorer<A> {
+  pub fn new(agent: A, start: A::Position, tile: A::Tile) -> Explorer<A> {
+    let mut explorer = Explorer {
+      agent,
+      position: start.clone(),
+      tiles: HashMap::new(),
+      passages: HashMap::new(),
+      blocked: vec![],
+      untried: HashMap::new()
+    };
+
+    explorer.visit(start, tile);
+    explorer
+  }
+
+  fn visit(&mut self, position: A::Position, tile: A::Tile) {
+    if !self.untried.contains_key(&position) {
+      let moves = self.agent.moves(&position, &tile);
+      self.untried.insert(position.clone(), moves);
+    }
+
+    self.tiles.insert(position, tile);
+  }
+
+  pub fn is_visited(&self, position: &A::Position) -> bool {
+    self.untried.contains_key(position)
+  }
+
+  pub fn is_explored(&self) -> bool {
+    self.untried.values().all(|moves| moves.is_empty())
+  }
+
+  pub fn path_to(&self, target: A::Position) -> Option<Vec<A::Position>> {
+    self.bfs(self.position.clone()).construct_path(target)
+  }
+
+  /// Walks on known passages to `target`. Returns `false` if there is no
+  /// known path or the agent didn't end up where the map said it would. The
+  /// map is corrected in that case, so the next walk takes another path.
+  pub fn walk_to(&mut self, target: A::Position) -> Result<bool, A::Error> {
+    let path = match self.path_to(target) {
+      Some(path) => path,
+      None => return Ok(false)
+    };
+
+    for next in path.into_iter().skip(1) {
+      let from = self.position.clone();
+      let step = self.passages[&from][&next].clone();
+
+      match self.agent.step(&from, &step)? {
+        Outcome::Moved(position, tile) => {
+          self.visit(position.clone(), tile);
+          self.position = position.clone();
+
+          if position != next {
+            let passages = self.passages.entry(from).or_default();
+
+            passages.remove(&next);
+            passages.insert(position, step);
+            return Ok(false);
+          }
+        },
+        Outcome::Blocked(revealed) => {
+          self.passages.entry(from.clone()).or_default().remove(&next);
+          self.blocked.push((from, step));
+
+          if let Some((position, tile)) = revealed {
+            self.tiles.insert(position, tile);
+          }
+
+          return Ok(false);
+        }
+      }
+    }
+
+    Ok(true)
+  }
+
+  /// Tries `step` from the current position and records the outcome.
+  pub fn try_move(&mut self, step: A::Move) -> Result<(), A::Error> {
+    let from = self.position.clone();
+
+    if let Some(target) = self.agent.target(&from, &step) {
+      if self.is_visited(&target) {
+        self.passages.entry(from).or_default().insert(target, step);
+        return Ok(());
+      } else if self.tiles.contains_key(&target) {
+        self.blocked.push((from, step));
+        return Ok(());
+      }
+    }
+
+    match self.agent.step(&from, &step)? {
+      Outcome::Moved(position, tile) => {
+        self.passages.entry(from).or_default().insert(position.clone(), step);
+        self.visit(position.clone(), tile);
+        self.position = position;
+      },
+      Outcome::Blocked(revealed) => {
+        self.blocked.push((from, step));
+
+        if let Some((position, tile)) = revealed {
+          self.tiles.insert(position, tile);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Explores until every move of every reachable position has been tried,
+  /// calling `on_step` after each move.
+  pub fn explore<F: FnMut(&Self)>(&mut self, mut on_step: F) -> Result<(), A::Error> {
+    loop {
+      let target = self.bfs(self.position.clone())
+        .find(|position| self.untried.get(position).is_some_and(|moves| !moves.is_empty()));
+
+      let target = match target {
+        Some(target) => target,
+        None => return Ok(())
+      };
+
+      // A failed walk changed the map, so the next target or path differs.
+      if !self.walk_to(target)? {
+        continue;
+      }
+
+      if let Some(step) = self.untried.get_mut(&self.position).and_then(|moves| moves.pop()) {
+        self.try_move(step)?;
+        on_step(self);
+      }
+    }
+  }
+}
+
+/// Renders positions in the bounding box of `tiles` row by row.
+pub fn render_grid<T, F>(tiles: &HashMap<GridPosition, T>, render: F) -> String
+where F: Fn(GridPosition, Option<&T>) -> char {
+  let mut result = String::new();
+  let bounds = tiles.keys().cloned()
+    .fold((None, None, None, None), |(min_x, max_x, min_y, max_y), (x, y)| {
+      (
+        [min_x, Some(x)].iter().filter_map(|&x| x).min(),
+        [max_x, Some(x)].iter().filter_map(|&x| x).max(),
+        [min_y, Some(y)].iter().filter_map(|&y| y).min(),
+        [max_y, Some(y)].iter().filter_map(|&y| y).max()
+      )
+    });
+
+  if let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = bounds {
+    for y in min_y..=max_y {
+      for x in min_x..=max_x {
+        result.push(render((x, y), tiles.get(&(x, y))));
+      }
+
+      result.push('\n');
+    }
+  }
+
+  result
+}
+
+#[cfg(test)]
+struct ClosingDoor {
+  crossings: usize
+}
+
+#[cfg(test)]
+impl Agent for ClosingDoor {
+  type Position = GridPosition;
+  type Move = GridPosition;
+  type Tile = char;
+  type Error = ();
+
+  fn moves(&self, _position: &GridPosition, _tile: &char) -> Vec<GridPosition> {
+    DIRECTIONS.to_vec()
+  }
+
+  // A corridor from (0, 0) to (2, 0) with a door between (0, 0) and (1, 0)
+  // that gets stuck after it has been walked through twice.
+  fn step(&mut self, &(x, y): &GridPosition, &(dx, dy): &GridPosition) -> Result<Outcome<GridPosition, char>, ()> {
+    let target = (x + dx, y + dy);
+
+    if target.1 != 0 || !(0..=2).contains(&target.0) {
+      return Ok(Outcome::Blocked(Some((target, '#'))));
+    }
+
+    if x + target.0 == 1 {
+      if self.crossings == 2 {
+        return Ok(Outcome::Blocked(None));
+      }
+
+      self.crossings += 1;
+    }
+
+    Ok(Outcome::Moved(target, '.'))
+  }
+}
+
+#[test]
+fn explore_closing_passage() {
+  let mut explorer = Explorer::new(ClosingDoor { crossings: 0 }, (0, 0), '.');
+
+  explorer.explore(|_| {}).unwrap();
+
+  assert_eq!(explorer.position, (0, 0));
+  assert!(explorer.passages[&(0, 0)].is_empty());
+  assert!(explorer.blocked.contains(&((0, 0), (1, 0))));
+  assert_eq!(explorer.path_to((2, 0)), None);
+}
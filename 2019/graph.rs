@@ -1,4 +1,7 @@
-use std::collections::{VecDeque, HashMap};
+#![allow(dead_code)]
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque, HashMap, HashSet};
 use std::hash::Hash;
 use std::iter;
 
@@ -10,6 +13,23 @@ enum NodeIterType {
 pub trait Vertex: Hash + Eq + Clone {}
 impl<T: Hash + Eq + Clone> Vertex for T {}
 
+/// Follows predecessors from `target` back to `start`.
+fn trace_path<V: Vertex>(predecessor_map: &HashMap<V, Option<V>>, start: &V, target: V) -> Option<Vec<V>> {
+  let mut path = vec![target];
+
+  while let Some(Some(previous)) = predecessor_map.get(path.last().unwrap()) {
+    path.push(previous.clone());
+  }
+
+  path.reverse();
+
+  if &path[0] == start {
+    Some(path)
+  } else {
+    None
+  }
+}
+
 pub struct PredecessorIter<'a, G: Graph<V>, V: Vertex> {
   node_iter: NodeIter<'a, G, V>
 }
@@ -61,19 +81,7 @@ impl<'a, G: Graph<V>, V: Vertex> NodeIter<'a, G, V> {
       self.find(|v| v == &target);
     }
 
-    let mut path = vec![target];
-
-    while let Some(Some(previous)) = self.predecessor_map.remove(path.last().unwrap()) {
-      path.push(previous);
-    }
-
-    path.reverse();
-
-    if path[0] == self.start {
-      Some(path)
-    } else {
-      None
-    }
+    trace_path(&self.predecessor_map, &self.start, target)
   }
 }
 
@@ -86,7 +94,7 @@ impl<'a, G: Graph<V>, V: Vertex> Iterator for NodeIter<'a, G, V> {
       NodeIterType::Dfs => self.queue.pop_back()
     };
 
-    vertex.map(|vertex| {
+    vertex.inspect(|vertex| {
       for neighbor in self.graph.get_neighbors(vertex.clone()) {
         if self.predecessor_map.contains_key(&neighbor) {
           continue;
@@ -95,12 +103,35 @@ impl<'a, G: Graph<V>, V: Vertex> Iterator for NodeIter<'a, G, V> {
         self.queue.push_back(neighbor.clone());
         self.predecessor_map.insert(neighbor, Some(vertex.clone()));
       }
-
-      vertex
     })
   }
 }
 
+/// The spanning tree of a depth-first traversal, see `Graph::depth_first`.
+#[derive(Debug, Clone)]
+pub struct DfsTree<V: Vertex> {
+  pub pre_order: Vec<V>,
+  pub post_order: Vec<V>,
+  start: V,
+  predecessor_map: HashMap<V, Option<V>>
+}
+
+impl<V: Vertex> DfsTree<V> {
+  /// Returns the path from the start to `target` along the tree.
+  pub fn construct_path(&self, target: V) -> Option<Vec<V>> {
+    trace_path(&self.predecessor_map, &self.start, target)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitedSearch<V> {
+  Found(Vec<V>),
+  /// No target within the depth limit, but there are paths beyond it.
+  CutOff,
+  /// No target reachable at all.
+  Exhausted
+}
+
 pub trait Graph<V: Vertex> {
   fn get_neighbors(&self, vertex: V) -> Vec<V>;
 
@@ -108,7 +139,925 @@ pub trait Graph<V: Vertex> {
     NodeIter::new(self, start, NodeIterType::Bfs)
   }
 
+  /// Like `bfs`, but takes the most recently discovered vertex first. As
+  /// vertices are marked when they're discovered, not when they're entered,
+  /// this isn't a true depth-first order, see `depth_first` for that.
   fn dfs<'a>(&'a self, start: V) -> NodeIter<'a, Self, V> where Self: Sized {
     NodeIter::new(self, start, NodeIterType::Dfs)
   }
+
+  /// Traverses the graph depth-first from `start`, calling `on_enter` with
+  /// each vertex and its depth in the tree before its descendants, and
+  /// `on_exit` after them.
+  fn depth_first<E, X>(&self, start: V, mut on_enter: E, mut on_exit: X) -> DfsTree<V>
+  where Self: Sized, E: FnMut(&V, usize), X: FnMut(&V, usize) {
+    let mut tree = DfsTree {
+      pre_order: vec![],
+      post_order: vec![],
+      start: start.clone(),
+      predecessor_map: iter::once((start.clone(), None)).collect()
+    };
+
+    on_enter(&start, 0);
+    tree.pre_order.push(start.clone());
+
+    let mut call_stack = vec![(start.clone(), self.get_neighbors(start), 0)];
+
+    while let Some((vertex, neighbors, i)) = call_stack.last_mut() {
+      if *i == neighbors.len() {
+        let depth = call_stack.len() - 1;
+        let (vertex, _, _) = call_stack.pop().unwrap();
+
+        on_exit(&vertex, depth);
+        tree.post_order.push(vertex);
+        continue;
+      }
+
+      let neighbor = neighbors[*i].clone();
+      *i += 1;
+
+      if tree.predecessor_map.contains_key(&neighbor) {
+        continue;
+      }
+
+      tree.predecessor_map.insert(neighbor.clone(), Some(vertex.clone()));
+      on_enter(&neighbor, call_stack.len());
+      tree.pre_order.push(neighbor.clone());
+      call_stack.push((neighbor.clone(), self.get_neighbors(neighbor), 0));
+    }
+
+    tree
+  }
+
+  /// Searches depth-first for a path of at most `limit` edges from `start`
+  /// to a vertex satisfying `is_target`. Only vertices on the current path
+  /// are avoided, so memory stays proportional to `limit`.
+  fn depth_limited_search<F>(&self, start: V, is_target: F, limit: usize) -> LimitedSearch<V>
+  where Self: Sized, F: Fn(&V) -> bool {
+    if is_target(&start) {
+      return LimitedSearch::Found(vec![start]);
+    }
+
+    let mut cut_off = false;
+    let mut path = vec![start.clone()];
+    let mut call_stack = vec![self.get_neighbors(start).into_iter()];
+
+    while let Some(neighbors) = call_stack.last_mut() {
+      let neighbor = match neighbors.next() {
+        Some(neighbor) => neighbor,
+        None => {
+          call_stack.pop();
+          path.pop();
+          continue;
+        }
+      };
+
+      if path.contains(&neighbor) {
+        continue;
+      }
+
+      path.push(neighbor.clone());
+
+      if is_target(&neighbor) {
+        return LimitedSearch::Found(path);
+      } else if path.len() > limit {
+        cut_off = cut_off || !self.get_neighbors(neighbor).is_empty();
+        path.pop();
+      } else {
+        call_stack.push(self.get_neighbors(neighbor).into_iter());
+      }
+    }
+
+    if cut_off {
+      LimitedSearch::CutOff
+    } else {
+      LimitedSearch::Exhausted
+    }
+  }
+
+  /// Runs `depth_limited_search` with increasing limits up to `max_depth`,
+  /// so the path found is a shortest one.
+  fn iterative_deepening<F>(&self, start: V, is_target: F, max_depth: usize) -> Option<Vec<V>>
+  where Self: Sized, F: Fn(&V) -> bool {
+    for limit in 0..=max_depth {
+      match self.depth_limited_search(start.clone(), &is_target, limit) {
+        LimitedSearch::Found(path) => return Some(path),
+        LimitedSearch::CutOff => continue,
+        LimitedSearch::Exhausted => return None
+      }
+    }
+
+    None
+  }
+
+  /// Returns the number of steps to every vertex reachable from `start`.
+  fn distances(&self, start: V) -> HashMap<V, usize> where Self: Sized {
+    self.nearest_sources(iter::once(start)).into_iter()
+    .map(|(v, (distance, _))| (v, distance))
+    .collect()
+  }
+
+  /// Searches breadth-first from all `sources` at once and returns the
+  /// distance to the nearest source for every reachable vertex, along with
+  /// that source.
+  fn nearest_sources<I: IntoIterator<Item = V>>(&self, sources: I) -> HashMap<V, (usize, V)> where Self: Sized {
+    let mut result = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for source in sources {
+      if !result.contains_key(&source) {
+        result.insert(source.clone(), (0, source.clone()));
+        queue.push_back(source);
+      }
+    }
+
+    while let Some(vertex) = queue.pop_front() {
+      let (distance, source) = result[&vertex].clone();
+
+      for neighbor in self.get_neighbors(vertex) {
+        if !result.contains_key(&neighbor) {
+          result.insert(neighbor.clone(), (distance + 1, source.clone()));
+          queue.push_back(neighbor);
+        }
+      }
+    }
+
+    result
+  }
+
+  /// Returns the distances between all pairs of `points` that are connected,
+  /// searching breadth-first once from each point.
+  fn all_pairs_distances(&self, points: &[V]) -> HashMap<(V, V), usize> where Self: Sized {
+    let mut result = HashMap::new();
+
+    for a in points {
+      let distances = self.distances(a.clone());
+
+      for b in points {
+        if let Some(&distance) = distances.get(b) {
+          result.insert((a.clone(), b.clone()), distance);
+        }
+      }
+    }
+
+    result
+  }
+
+  /// Contracts the graph to a weighted graph on `points`. Two points are
+  /// connected if there is a path between them which doesn't pass another
+  /// point, weighted by the length of the shortest such path.
+  fn contract(&self, points: &[V]) -> ContractedGraph<V> where Self: Sized {
+    let point_set = points.iter().cloned().collect::<HashSet<_>>();
+    let mut edges = HashMap::new();
+
+    for point in points {
+      let mut distances = iter::once((point.clone(), 0)).collect::<HashMap<_, _>>();
+      let mut queue = iter::once(point.clone()).collect::<VecDeque<_>>();
+      let mut neighbors = vec![];
+
+      while let Some(vertex) = queue.pop_front() {
+        let distance = distances[&vertex];
+
+        for neighbor in self.get_neighbors(vertex) {
+          if distances.contains_key(&neighbor) {
+            continue;
+          }
+
+          distances.insert(neighbor.clone(), distance + 1);
+
+          if point_set.contains(&neighbor) {
+            neighbors.push((neighbor, distance + 1));
+          } else {
+            queue.push_back(neighbor);
+          }
+        }
+      }
+
+      edges.insert(point.clone(), neighbors);
+    }
+
+    ContractedGraph { edges }
+  }
+
+  /// Returns `starts` and every vertex reachable from them in breadth-first
+  /// order.
+  fn reachable(&self, starts: &[V]) -> Vec<V> where Self: Sized {
+    let mut visited = HashSet::new();
+    let mut result = starts.iter()
+      .filter(|&v| visited.insert(v.clone()))
+      .cloned()
+      .collect::<Vec<_>>();
+    let mut i = 0;
+
+    while i < result.len() {
+      for neighbor in self.get_neighbors(result[i].clone()) {
+        if visited.insert(neighbor.clone()) {
+          result.push(neighbor);
+        }
+      }
+
+      i += 1;
+    }
+
+    result
+  }
+
+  /// Returns the connected components of the vertices reachable from
+  /// `vertices`, ignoring edge directions. Components are ordered by their
+  /// first vertex in `reachable` order.
+  fn connected_components(&self, vertices: &[V]) -> Vec<Vec<V>> where Self: Sized {
+    let vertices = self.reachable(vertices);
+    let mut union_find = UnionFind::new();
+
+    for vertex in &vertices {
+      for neighbor in self.get_neighbors(vertex.clone()) {
+        union_find.union(vertex.clone(), neighbor);
+      }
+    }
+
+    let mut components = HashMap::new();
+    let mut result: Vec<Vec<V>> = vec![];
+
+    for vertex in vertices {
+      let root = union_find.find(vertex.clone());
+      let index = *components.entry(root).or_insert_with(|| {
+        result.push(vec![]);
+        result.len() - 1
+      });
+
+      result[index].push(vertex);
+    }
+
+    result
+  }
+
+  /// Returns the strongly connected components of the vertices reachable
+  /// from `vertices` using Tarjan's algorithm. Components come in reverse
+  /// topological order, i.e. no component has edges into a later one.
+  fn strongly_connected_components(&self, vertices: &[V]) -> Vec<Vec<V>> where Self: Sized {
+    let mut counter = 0;
+    let mut indices = HashMap::new();
+    let mut lowlinks = HashMap::new();
+    let mut stack = vec![];
+    let mut on_stack = HashSet::new();
+    let mut result = vec![];
+
+    for root in vertices {
+      if indices.contains_key(root) {
+        continue;
+      }
+
+      // Frames of the depth-first search hold a vertex, its neighbors and
+      // the index of the next neighbor to visit
+      let mut call_stack: Vec<(V, Vec<V>, usize)> = vec![];
+      let mut next = Some(root.clone());
+
+      loop {
+        if let Some(vertex) = next.take() {
+          indices.insert(vertex.clone(), counter);
+          lowlinks.insert(vertex.clone(), counter);
+          counter += 1;
+          stack.push(vertex.clone());
+          on_stack.insert(vertex.clone());
+          call_stack.push((vertex.clone(), self.get_neighbors(vertex), 0));
+        }
+
+        let (vertex, neighbors, i) = match call_stack.last_mut() {
+          Some(frame) => frame,
+          None => break
+        };
+
+        if *i < neighbors.len() {
+          let neighbor = neighbors[*i].clone();
+          *i += 1;
+
+          if !indices.contains_key(&neighbor) {
+            next = Some(neighbor);
+          } else if on_stack.contains(&neighbor) {
+            let lowlink = lowlinks[&*vertex].min(indices[&neighbor]);
+            lowlinks.insert(vertex.clone(), lowlink);
+          }
+
+          continue;
+        }
+
+        let vertex = vertex.clone();
+        call_stack.pop();
+
+        if let Some((parent, _, _)) = call_stack.last() {
+          let lowlink = lowlinks[parent].min(lowlinks[&vertex]);
+          lowlinks.insert(parent.clone(), lowlink);
+        }
+
+        if lowlinks[&vertex] == indices[&vertex] {
+          let mut component = vec![];
+
+          while let Some(member) = stack.pop() {
+            on_stack.remove(&member);
+            component.push(member.clone());
+
+            if member == vertex {
+              break;
+            }
+          }
+
+          component.reverse();
+          result.push(component);
+        }
+      }
+    }
+
+    result
+  }
+
+  /// Finds a directed cycle among the vertices reachable from `vertices`.
+  /// The returned cycle has an edge from each vertex to the next and from
+  /// the last vertex back to the first.
+  fn find_cycle(&self, vertices: &[V]) -> Option<Vec<V>> where Self: Sized {
+    // `false` while a vertex is on the current path, `true` once it's done
+    let mut finished = HashMap::new();
+
+    for root in vertices {
+      if finished.contains_key(root) {
+        continue;
+      }
+
+      let mut call_stack = vec![(root.clone(), self.get_neighbors(root.clone()), 0)];
+      finished.insert(root.clone(), false);
+
+      while let Some((vertex, neighbors, i)) = call_stack.last_mut() {
+        if *i == neighbors.len() {
+          finished.insert(vertex.clone(), true);
+          call_stack.pop();
+          continue;
+        }
+
+        let neighbor = neighbors[*i].clone();
+        *i += 1;
+
+        match finished.get(&neighbor) {
+          Some(false) => {
+            let start = call_stack.iter().position(|(v, _, _)| v == &neighbor).unwrap();
+
+            return Some(call_stack.drain(start..).map(|(v, _, _)| v).collect());
+          },
+          Some(true) => {},
+          None => {
+            finished.insert(neighbor.clone(), false);
+            call_stack.push((neighbor.clone(), self.get_neighbors(neighbor), 0));
+          }
+        }
+      }
+    }
+
+    None
+  }
+
+  /// Sorts the vertices reachable from `vertices` topologically with Kahn's
+  /// algorithm, always taking the smallest available vertex next. Returns a
+  /// cycle if there is no such order.
+  fn topological_sort(&self, vertices: &[V]) -> Result<Vec<V>, Vec<V>> where Self: Sized, V: Ord {
+    let vertices = self.reachable(vertices);
+    let mut in_degrees = vertices.iter().map(|v| (v.clone(), 0)).collect::<HashMap<_, _>>();
+
+    for vertex in &vertices {
+      for neighbor in self.get_neighbors(vertex.clone()) {
+        *in_degrees.get_mut(&neighbor).unwrap() += 1;
+      }
+    }
+
+    let mut heap = in_degrees.iter()
+      .filter(|&(_, &degree)| degree == 0)
+      .map(|(v, _)| Reverse(v.clone()))
+      .collect::<BinaryHeap<_>>();
+    let mut result = vec![];
+
+    while let Some(Reverse(vertex)) = heap.pop() {
+      for neighbor in self.get_neighbors(vertex.clone()) {
+        let degree = in_degrees.get_mut(&neighbor).unwrap();
+        *degree -= 1;
+
+        if *degree == 0 {
+          heap.push(Reverse(neighbor));
+        }
+      }
+
+      result.push(vertex);
+    }
+
+    if result.len() == vertices.len() {
+      Ok(result)
+    } else {
+      let remaining = vertices.into_iter()
+        .filter(|v| in_degrees[v] > 0)
+        .collect::<Vec<_>>();
+
+      Err(self.find_cycle(&remaining).unwrap_or_default())
+    }
+  }
+}
+
+/// Disjoint sets of vertices. Vertices that were never united form sets of
+/// their own.
+#[derive(Debug, Clone)]
+pub struct UnionFind<V: Vertex> {
+  parents: HashMap<V, V>,
+  sizes: HashMap<V, usize>
+}
+
+impl<V: Vertex> UnionFind<V> {
+  pub fn new() -> UnionFind<V> {
+    UnionFind {
+      parents: HashMap::new(),
+      sizes: HashMap::new()
+    }
+  }
+
+  /// Returns the representative of the set containing `vertex`.
+  pub fn find(&mut self, vertex: V) -> V {
+    let mut root = vertex.clone();
+
+    while let Some(parent) = self.parents.get(&root) {
+      root = parent.clone();
+    }
+
+    // Compress the path, so later lookups are fast
+    let mut vertex = vertex;
+
+    while vertex != root {
+      let parent = self.parents.insert(vertex, root.clone()).unwrap();
+      vertex = parent;
+    }
+
+    root
+  }
+
+  /// Unites the sets containing `a` and `b`. Returns `false` if they were
+  /// already the same set.
+  pub fn union(&mut self, a: V, b: V) -> bool {
+    let (a, b) = (self.find(a), self.find(b));
+
+    if a == b {
+      return false;
+    }
+
+    let size_a = self.sizes.get(&a).cloned().unwrap_or(1);
+    let size_b = self.sizes.get(&b).cloned().unwrap_or(1);
+    let (small, large) = if size_a < size_b { (a, b) } else { (b, a) };
+
+    self.sizes.remove(&small);
+    self.sizes.insert(large.clone(), size_a + size_b);
+    self.parents.insert(small, large);
+    true
+  }
+
+  pub fn is_same_set(&mut self, a: V, b: V) -> bool {
+    self.find(a) == self.find(b)
+  }
+}
+
+/// A graph that can also be walked backwards, e.g. an undirected graph
+/// where reverse neighbors are just neighbors.
+pub trait ReversibleGraph<V: Vertex>: Graph<V> {
+  fn get_reverse_neighbors(&self, vertex: V) -> Vec<V>;
+
+  /// Finds a shortest path by searching breadth-first from both ends,
+  /// always expanding the smaller frontier by one layer.
+  fn bidirectional_bfs(&self, start: V, target: V) -> Option<Vec<V>> where Self: Sized {
+    if start == target {
+      return Some(vec![start]);
+    }
+
+    // Each side maps vertices to their neighbor towards its end and their
+    // distance from it
+    let mut forward = iter::once((start.clone(), (None, 0))).collect::<HashMap<_, _>>();
+    let mut backward = iter::once((target.clone(), (None, 0))).collect::<HashMap<_, _>>();
+    let mut forward_frontier = vec![start];
+    let mut backward_frontier = vec![target];
+
+    while !forward_frontier.is_empty() && !backward_frontier.is_empty() {
+      let meeting = if forward_frontier.len() <= backward_frontier.len() {
+        expand_layer(&mut forward_frontier, &mut forward, &backward, |v| self.get_neighbors(v))
+      } else {
+        expand_layer(&mut backward_frontier, &mut backward, &forward, |v| self.get_reverse_neighbors(v))
+      };
+
+      if let Some(meeting) = meeting {
+        let mut path = vec![meeting.clone()];
+
+        while let Some((Some(previous), _)) = forward.get(path.last().unwrap()) {
+          path.push(previous.clone());
+        }
+
+        path.reverse();
+
+        while let Some((Some(next), _)) = backward.get(path.last().unwrap()) {
+          path.push(next.clone());
+        }
+
+        return Some(path);
+      }
+    }
+
+    None
+  }
+}
+
+/// Expands `frontier` by one layer and returns the discovered vertex known
+/// to `other` that is closest to both ends.
+fn expand_layer<V: Vertex, F: Fn(V) -> Vec<V>>(
+  frontier: &mut Vec<V>,
+  visited: &mut HashMap<V, (Option<V>, usize)>,
+  other: &HashMap<V, (Option<V>, usize)>,
+  get_neighbors: F
+) -> Option<V> {
+  let mut next_frontier = vec![];
+  let mut meeting: Option<(usize, V)> = None;
+
+  for vertex in frontier.drain(..) {
+    let distance = visited[&vertex].1 + 1;
+
+    for neighbor in get_neighbors(vertex.clone()) {
+      if visited.contains_key(&neighbor) {
+        continue;
+      }
+
+      visited.insert(neighbor.clone(), (Some(vertex.clone()), distance));
+
+      if let Some(&(_, other_distance)) = other.get(&neighbor) {
+        if meeting.as_ref().is_none_or(|(total, _)| distance + other_distance < *total) {
+          meeting = Some((distance + other_distance, neighbor.clone()));
+        }
+      }
+
+      next_frontier.push(neighbor);
+    }
+  }
+
+  *frontier = next_frontier;
+  meeting.map(|(_, v)| v)
+}
+
+/// A weighted graph on points of interest of a larger graph, see
+/// `Graph::contract`.
+#[derive(Debug, Clone)]
+pub struct ContractedGraph<V: Vertex> {
+  pub edges: HashMap<V, Vec<(V, usize)>>
+}
+
+impl<V: Vertex> WeightedGraph<V> for ContractedGraph<V> {
+  fn get_weighted_neighbors(&self, vertex: V) -> Vec<(V, usize)> {
+    self.edges.get(&vertex).cloned().unwrap_or_default()
+  }
+}
+
+struct HeapEntry<V> {
+  priority: usize,
+  cost: usize,
+  vertex: V,
+  predecessor: Option<V>
+}
+
+impl<V> PartialEq for HeapEntry<V> {
+  fn eq(&self, other: &Self) -> bool {
+    self.priority == other.priority
+  }
+}
+
+impl<V> Eq for HeapEntry<V> {}
+
+impl<V> PartialOrd for HeapEntry<V> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<V> Ord for HeapEntry<V> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // Reversed, so the binary heap pops the lowest priority first
+    other.priority.cmp(&self.priority)
+  }
+}
+
+/// Yields vertices along with their distance from the start in order of
+/// distance plus heuristic. The heuristic must never overestimate the
+/// distance to any target. If it's not consistent either, i.e. it drops by
+/// more than the weight of some edge, vertices may be yielded again once a
+/// cheaper path to them turns up.
+pub struct WeightedNodeIter<'a, G: WeightedGraph<V>, V: Vertex, H: Fn(&V) -> usize> {
+  graph: &'a G,
+  start: V,
+  heuristic: H,
+  heap: BinaryHeap<HeapEntry<V>>,
+  costs: HashMap<V, usize>,
+  distances: HashMap<V, usize>,
+  predecessor_map: HashMap<V, Option<V>>
+}
+
+impl<'a, G: WeightedGraph<V>, V: Vertex, H: Fn(&V) -> usize> WeightedNodeIter<'a, G, V, H> {
+  fn new(graph: &'a G, start: V, heuristic: H) -> WeightedNodeIter<'a, G, V, H> {
+    let entry = HeapEntry {
+      priority: heuristic(&start),
+      cost: 0,
+      vertex: start.clone(),
+      predecessor: None
+    };
+
+    WeightedNodeIter {
+      graph,
+      start: start.clone(),
+      heuristic,
+      heap: iter::once(entry).collect(),
+      costs: iter::once((start, 0)).collect(),
+      distances: HashMap::new(),
+      predecessor_map: HashMap::new()
+    }
+  }
+
+  pub fn distance(&self, vertex: &V) -> Option<usize> {
+    self.distances.get(vertex).cloned()
+  }
+
+  /// Returns the total cost and a cheapest path from the start to `target`.
+  pub fn construct_path(mut self, target: V) -> Option<(usize, Vec<V>)> {
+    if !self.distances.contains_key(&target) {
+      self.find(|(v, _)| v == &target);
+    }
+
+    let cost = self.distance(&target)?;
+
+    trace_path(&self.predecessor_map, &self.start, target).map(|path| (cost, path))
+  }
+}
+
+impl<'a, G: WeightedGraph<V>, V: Vertex, H: Fn(&V) -> usize> Iterator for WeightedNodeIter<'a, G, V, H> {
+  type Item = (V, usize);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(entry) = self.heap.pop() {
+      // Skip vertices that are final or were reached more cheaply since
+      if self.distances.contains_key(&entry.vertex) || self.costs[&entry.vertex] < entry.cost {
+        continue;
+      }
+
+      self.distances.insert(entry.vertex.clone(), entry.cost);
+      self.predecessor_map.insert(entry.vertex.clone(), entry.predecessor);
+
+      for (neighbor, weight) in self.graph.get_weighted_neighbors(entry.vertex.clone()) {
+        let cost = entry.cost + weight;
+
+        if self.costs.get(&neighbor).is_some_and(|&known| known <= cost) {
+          continue;
+        }
+
+        // With an inconsistent heuristic, a vertex can be reached more
+        // cheaply after it was final, so it has to be reopened
+        self.distances.remove(&neighbor);
+        self.costs.insert(neighbor.clone(), cost);
+        self.heap.push(HeapEntry {
+          priority: cost + (self.heuristic)(&neighbor),
+          cost,
+          vertex: neighbor,
+          predecessor: Some(entry.vertex.clone())
+        });
+      }
+
+      return Some((entry.vertex, entry.cost));
+    }
+
+    None
+  }
+}
+
+pub trait WeightedGraph<V: Vertex> {
+  /// Returns the neighbors of `vertex` along with the costs of the edges
+  /// leading there.
+  fn get_weighted_neighbors(&self, vertex: V) -> Vec<(V, usize)>;
+
+  fn dijkstra<'a>(&'a self, start: V) -> WeightedNodeIter<'a, Self, V, fn(&V) -> usize> where Self: Sized {
+    WeightedNodeIter::new(self, start, |_| 0)
+  }
+
+  /// Finds a cheapest path to `target`, guided by `heuristic`, an estimate
+  /// of the remaining cost that must never be too high. Inconsistent
+  /// heuristics are fine, but may cause vertices to be expanded repeatedly.
+  fn a_star<H: Fn(&V) -> usize>(&self, start: V, target: V, heuristic: H) -> Option<(usize, Vec<V>)> where Self: Sized {
+    WeightedNodeIter::new(self, start, heuristic).construct_path(target)
+  }
+}
+
+/// Computes the distances between all connected pairs of `vertices` at
+/// once, only following edges between them. Preferable to searching from
+/// each vertex when the graph is small and dense.
+pub fn floyd_warshall<G: WeightedGraph<V>, V: Vertex>(graph: &G, vertices: &[V]) -> HashMap<(V, V), usize> {
+  let indices = vertices.iter().enumerate()
+    .map(|(i, v)| (v.clone(), i))
+    .collect::<HashMap<_, _>>();
+  let mut distances = vec![vec![None; vertices.len()]; vertices.len()];
+
+  for (i, vertex) in vertices.iter().enumerate() {
+    distances[i][i] = Some(0);
+
+    for (neighbor, weight) in graph.get_weighted_neighbors(vertex.clone()) {
+      if let Some(&j) = indices.get(&neighbor) {
+        distances[i][j] = [distances[i][j], Some(weight)].iter().filter_map(|&d| d).min();
+      }
+    }
+  }
+
+  for k in 0..vertices.len() {
+    for i in 0..vertices.len() {
+      for j in 0..vertices.len() {
+        if let (Some(a), Some(b)) = (distances[i][k], distances[k][j]) {
+          if distances[i][j].is_none_or(|d| a + b < d) {
+            distances[i][j] = Some(a + b);
+          }
+        }
+      }
+    }
+  }
+
+  distances.into_iter().enumerate()
+  .flat_map(|(i, row)| row.into_iter().enumerate().filter_map(move |(j, d)| d.map(|d| (i, j, d))))
+  .map(|(i, j, d)| ((vertices[i].clone(), vertices[j].clone()), d))
+  .collect()
+}
+
+#[test]
+fn weighted_shortest_paths() {
+  struct Grid(Vec<Vec<usize>>);
+
+  impl WeightedGraph<(usize, usize)> for Grid {
+    fn get_weighted_neighbors(&self, (x, y): (usize, usize)) -> Vec<((usize, usize), usize)> {
+      [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].iter()
+      .filter_map(|&(x, y)| self.0.get(y).and_then(|row| row.get(x)).map(|&cost| ((x, y), cost)))
+      .collect()
+    }
+  }
+
+  let grid = Grid(vec![
+    vec![1, 9, 1, 1],
+    vec![1, 9, 1, 9],
+    vec![1, 1, 1, 1]
+  ]);
+
+  let (cost, path) = grid.dijkstra((0, 0)).construct_path((3, 0)).unwrap();
+  assert_eq!(cost, 7);
+  assert_eq!(path, vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0), (3, 0)]);
+
+  let manhattan = |&(x, y): &(usize, usize)| (3 - x) + y;
+  assert_eq!(grid.a_star((0, 0), (3, 0), manhattan), Some((cost, path)));
+
+  let distances = grid.dijkstra((0, 0)).map(|(_, cost)| cost).collect::<Vec<_>>();
+  assert!(distances.windows(2).all(|w| w[0] <= w[1]));
+  assert_eq!(grid.dijkstra((0, 0)).construct_path((5, 5)), None);
+}
+
+#[test]
+fn inconsistent_heuristic() {
+  // S -> A -> C -> G is cheapest, but A looks expensive, so C is reached
+  // through S -> C first and has to be reopened
+  struct Edges;
+
+  impl WeightedGraph<char> for Edges {
+    fn get_weighted_neighbors(&self, vertex: char) -> Vec<(char, usize)> {
+      match vertex {
+        'S' => vec![('A', 1), ('C', 3)],
+        'A' => vec![('C', 1)],
+        'C' => vec![('G', 3)],
+        _ => vec![]
+      }
+    }
+  }
+
+  let heuristic = |&v: &char| if v == 'A' { 4 } else { 0 };
+
+  assert_eq!(Edges.a_star('S', 'G', heuristic), Some((5, vec!['S', 'A', 'C', 'G'])));
+}
+
+#[test]
+fn distances_and_contraction() {
+  // A path 0 - 1 - ... - 9 with a shortcut 2 - 7
+  struct Path;
+
+  impl Graph<u32> for Path {
+    fn get_neighbors(&self, v: u32) -> Vec<u32> {
+      let mut result = vec![v.wrapping_sub(1), v + 1].into_iter().filter(|&w| w < 10).collect::<Vec<_>>();
+
+      match v {
+        2 => result.push(7),
+        7 => result.push(2),
+        _ => {}
+      }
+
+      result
+    }
+  }
+
+  assert_eq!(Path.distances(0)[&9], 5);
+  assert_eq!(Path.nearest_sources(vec![0, 9])[&5], (4, 9));
+  assert_eq!(Path.nearest_sources(vec![0, 9])[&3], (3, 0));
+
+  let points = [0, 4, 9];
+  let all_pairs = Path.all_pairs_distances(&points);
+  assert_eq!(all_pairs[&(0, 9)], 5);
+  assert_eq!(all_pairs[&(4, 9)], 5);
+
+  let contracted = Path.contract(&points);
+  let mut edges = contracted.get_weighted_neighbors(9);
+  edges.sort();
+  assert_eq!(edges, vec![(0, 5), (4, 5)]);
+
+  assert_eq!(floyd_warshall(&contracted, &points), all_pairs);
+}
+
+#[test]
+fn components_cycles_and_order() {
+  // The example of 2018/07
+  #[derive(Clone)]
+  struct Steps(HashMap<char, Vec<char>>);
+
+  impl Graph<char> for Steps {
+    fn get_neighbors(&self, v: char) -> Vec<char> {
+      self.0.get(&v).cloned().unwrap_or_default()
+    }
+  }
+
+  let graph = Steps(vec![
+    ('C', vec!['A', 'F']),
+    ('A', vec!['B', 'D']),
+    ('B', vec!['E']),
+    ('D', vec!['E']),
+    ('F', vec!['E']),
+    ('X', vec![])
+  ].into_iter().collect());
+
+  assert_eq!(graph.topological_sort(&['C', 'X']), Ok("CABDFEX".chars().collect()));
+  assert_eq!(graph.find_cycle(&['C']), None);
+  assert_eq!(graph.connected_components(&['C', 'X']), vec![vec!['C', 'A', 'F', 'B', 'D', 'E'], vec!['X']]);
+
+  // Adding E -> G -> F closes the cycle F -> E -> G -> F
+  let mut cyclic = graph.clone();
+  cyclic.0.insert('E', vec!['G']);
+  cyclic.0.insert('G', vec!['F']);
+
+  let cycle = cyclic.find_cycle(&['C']).unwrap();
+  assert_eq!(cycle.len(), 3);
+  assert!(cycle.iter().zip(cycle.iter().cycle().skip(1)).all(|(&a, &b)| cyclic.get_neighbors(a).contains(&b)));
+  assert_eq!(cyclic.topological_sort(&['C']).unwrap_err().len(), 3);
+
+  let mut components = cyclic.strongly_connected_components(&['C']);
+  components[0].sort();
+  assert_eq!(components.len(), 5);
+  assert_eq!(components[0], vec!['E', 'F', 'G']);
+  assert_eq!(components.last(), Some(&vec!['C']));
+
+  let mut union_find = UnionFind::new();
+  assert!(union_find.union(1, 2));
+  assert!(union_find.union(3, 2));
+  assert!(!union_find.union(1, 3));
+  assert!(!union_find.is_same_set(1, 4));
+}
+
+#[test]
+fn depth_first_searches() {
+  // A binary tree with vertex `v` having children `2v` and `2v + 1` and an
+  // edge back to the root from 5
+  struct Tree;
+
+  impl Graph<u32> for Tree {
+    fn get_neighbors(&self, v: u32) -> Vec<u32> {
+      let mut result = vec![2 * v, 2 * v + 1].into_iter().filter(|&w| w < 16).collect::<Vec<_>>();
+
+      if v == 5 {
+        result.push(1);
+      }
+
+      result
+    }
+  }
+
+  impl ReversibleGraph<u32> for Tree {
+    fn get_reverse_neighbors(&self, v: u32) -> Vec<u32> {
+      if v > 1 { vec![v / 2] } else { vec![5] }
+    }
+  }
+
+  let mut events = vec![];
+  let tree = Tree.depth_first(2, |&v, depth| events.push((v, depth)), |_, _| {});
+
+  assert_eq!(events, vec![(2, 0), (4, 1), (8, 2), (9, 2), (5, 1), (10, 2), (11, 2), (1, 2), (3, 3), (6, 4), (12, 5), (13, 5), (7, 4), (14, 5), (15, 5)]);
+  assert_eq!(tree.post_order[..3], [8, 9, 4]);
+  assert_eq!(tree.post_order.last(), Some(&2));
+  assert_eq!(tree.construct_path(6), Some(vec![2, 5, 1, 3, 6]));
+
+  assert_eq!(Tree.depth_limited_search(1, |&v| v == 11, 2), LimitedSearch::CutOff);
+  assert_eq!(Tree.depth_limited_search(1, |&v| v == 11, 3), LimitedSearch::Found(vec![1, 2, 5, 11]));
+  assert_eq!(Tree.depth_limited_search(8, |&v| v == 1, 3), LimitedSearch::Exhausted);
+  assert_eq!(Tree.iterative_deepening(4, |&v| v == 1, 10), None);
+  assert_eq!(Tree.iterative_deepening(2, |&v| v > 12, 10), Some(vec![2, 5, 1, 3, 6, 13]));
+
+  assert_eq!(Tree.bidirectional_bfs(1, 14), Some(vec![1, 3, 7, 14]));
+  assert_eq!(Tree.bidirectional_bfs(11, 15), None);
+  assert_eq!(Tree.bidirectional_bfs(10, 10), Some(vec![10]));
 }
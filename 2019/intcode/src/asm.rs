@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::{error, fmt};
+
+use crate::instruction::{OperationType, ParameterMode};
+
+const MAX_MACRO_DEPTH: usize = 16;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AsmError {
+  pub line: usize,
+  pub message: String
+}
+
+impl fmt::Display for AsmError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl error::Error for AsmError {}
+
+fn fail<T>(line: usize, message: String) -> Result<T, AsmError> {
+  Err(AsmError { line, message })
+}
+
+#[derive(Debug, Clone)]
+struct Expression {
+  label: Option<String>,
+  offset: i64
+}
+
+#[derive(Debug, Clone)]
+enum DataItem {
+  Value(Expression),
+  Text(String)
+}
+
+#[derive(Debug)]
+enum Statement {
+  Instruction(OperationType, Vec<(ParameterMode, Expression)>),
+  Data(Vec<DataItem>)
+}
+
+struct Macro {
+  params: Vec<String>,
+  body: Vec<String>
+}
+
+fn get_operation(mnemonic: &str) -> Option<OperationType> {
+  Some(match mnemonic.to_lowercase().as_str() {
+    "add" => OperationType::Add,
+    "mul" => OperationType::Multiply,
+    "in" => OperationType::Input,
+    "out" => OperationType::Output,
+    "jt" => OperationType::JumpIfTrue,
+    "jf" => OperationType::JumpIfFalse,
+    "lt" => OperationType::LessThan,
+    "eq" => OperationType::Equals,
+    "arb" => OperationType::SetRelativeBase,
+    "hlt" => OperationType::Halt,
+    _ => return None
+  })
+}
+
+fn is_identifier(token: &str) -> bool {
+  let mut chars = token.chars();
+
+  match chars.next() {
+    Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
+      chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    },
+    _ => false
+  }
+}
+
+fn strip_comment(line: &str) -> &str {
+  let mut in_string = false;
+
+  for (i, c) in line.char_indices() {
+    match c {
+      '"' => in_string = !in_string,
+      ';' if !in_string => return &line[..i],
+      _ => {}
+    }
+  }
+
+  line
+}
+
+fn split_arguments(text: &str) -> Vec<String> {
+  let mut result = vec![];
+  let mut current = String::new();
+  let mut in_string = false;
+  let mut depth = 0;
+
+  for c in text.chars() {
+    match c {
+      '"' => in_string = !in_string,
+      '[' if !in_string => depth += 1,
+      ']' if !in_string => depth -= 1,
+      ',' if !in_string && depth == 0 => {
+        result.push(current.trim().to_owned());
+        current.clear();
+        continue;
+      },
+      _ => {}
+    }
+
+    current.push(c);
+  }
+
+  if !current.trim().is_empty() || !result.is_empty() {
+    result.push(current.trim().to_owned());
+  }
+
+  result
+}
+
+fn split_mnemonic(text: &str) -> (&str, &str) {
+  match text.find(char::is_whitespace) {
+    Some(i) => (&text[..i], text[i..].trim()),
+    None => (text, "")
+  }
+}
+
+fn split_labels(text: &str) -> (&str, &str) {
+  let mut rest = text.trim();
+
+  while let Some(i) = rest.find(':') {
+    if !is_identifier(rest[..i].trim()) {
+      break;
+    }
+
+    rest = rest[i + 1..].trim();
+  }
+
+  (&text[..text.len() - rest.len()], rest)
+}
+
+fn expand_macros(source: &str) -> Result<Vec<(usize, String)>, AsmError> {
+  fn expand(
+    line: usize,
+    text: &str,
+    macros: &HashMap<String, Macro>,
+    depth: usize,
+    expansion_count: &mut usize,
+    result: &mut Vec<(usize, String)>
+  ) -> Result<(), AsmError> {
+    let text = strip_comment(text).trim();
+    let (labels, statement) = split_labels(text);
+    let (name, arguments) = split_mnemonic(statement);
+
+    let m = match macros.get(&name.to_lowercase()) {
+      Some(m) => m,
+      None => {
+        result.push((line, text.to_owned()));
+        return Ok(());
+      }
+    };
+
+    if !labels.is_empty() {
+      result.push((line, labels.to_owned()));
+    }
+
+    if depth >= MAX_MACRO_DEPTH {
+      return fail(line, format!("macro '{}' nested too deeply", name));
+    }
+
+    let arguments = split_arguments(arguments);
+
+    if arguments.len() != m.params.len() {
+      return fail(line, format!(
+        "macro '{}' expects {} arguments, got {}",
+        name, m.params.len(), arguments.len()
+      ));
+    }
+
+    *expansion_count += 1;
+    let unique = expansion_count.to_string();
+
+    let mut substitutions = m.params.iter().zip(arguments.iter()).collect::<Vec<_>>();
+    substitutions.sort_by_key(|(param, _)| std::cmp::Reverse(param.len()));
+
+    for body_line in &m.body {
+      let mut body_line = body_line.replace("$$", &unique);
+
+      for (param, argument) in &substitutions {
+        body_line = body_line.replace(&format!("${}", param), argument);
+      }
+
+      expand(line, &body_line, macros, depth + 1, expansion_count, result)?;
+    }
+
+    Ok(())
+  }
+
+  let mut macros = HashMap::new();
+  let mut current_macro: Option<(usize, String, Macro)> = None;
+  let mut expansion_count = 0;
+  let mut result = vec![];
+
+  for (i, text) in source.lines().enumerate() {
+    let line = i + 1;
+    let (directive, arguments) = split_mnemonic(strip_comment(text).trim());
+
+    match directive.to_lowercase().as_str() {
+      ".macro" => {
+        if current_macro.is_some() {
+          return fail(line, "nested macro definition".to_owned());
+        }
+
+        let (name, params) = split_mnemonic(arguments);
+
+        if !is_identifier(name) || get_operation(name).is_some() {
+          return fail(line, format!("invalid macro name '{}'", name));
+        }
+
+        let params = split_arguments(params);
+
+        if let Some(param) = params.iter().find(|param| !is_identifier(param)) {
+          return fail(line, format!("invalid macro parameter '{}'", param));
+        }
+
+        current_macro = Some((line, name.to_lowercase(), Macro { params, body: vec![] }));
+      },
+      ".endm" => match current_macro.take() {
+        Some((_, name, m)) => {
+          macros.insert(name, m);
+        },
+        None => return fail(line, ".endm without .macro".to_owned())
+      },
+      _ => match current_macro.as_mut() {
+        Some((_, _, m)) => m.body.push(text.to_owned()),
+        None => expand(line, text, &macros, 0, &mut expansion_count, &mut result)?
+      }
+    }
+  }
+
+  if let Some((line, name, _)) = current_macro {
+    return fail(line, format!("macro '{}' is missing .endm", name));
+  }
+
+  Ok(result)
+}
+
+fn parse_expression(line: usize, text: &str) -> Result<Expression, AsmError> {
+  let text = text.trim();
+
+  if let Ok(offset) = text.parse::<i64>() {
+    return Ok(Expression { label: None, offset });
+  }
+
+  let (label, offset) = match text.rfind(['+', '-']) {
+    Some(i) if i > 0 => {
+      let offset = text[i + 1..].trim().parse::<i64>()
+        .or_else(|_| fail(line, format!("invalid offset in '{}'", text)))?;
+
+      (text[..i].trim(), if &text[i..i + 1] == "-" { -offset } else { offset })
+    },
+    _ => (text, 0)
+  };
+
+  if !is_identifier(label) {
+    return fail(line, format!("invalid expression '{}'", text));
+  }
+
+  Ok(Expression { label: Some(label.to_owned()), offset })
+}
+
+fn parse_operand(line: usize, text: &str) -> Result<(ParameterMode, Expression), AsmError> {
+  if let Some(value) = text.strip_prefix('#') {
+    return Ok((ParameterMode::Immediate, parse_expression(line, value)?));
+  }
+
+  let inner = match text.strip_prefix('[').and_then(|text| text.strip_suffix(']')) {
+    Some(inner) => inner.trim(),
+    None => return fail(line, format!("invalid operand '{}'", text))
+  };
+
+  let relative = inner.strip_prefix("rb")
+    .filter(|rest| rest.trim().is_empty() || rest.trim_start().starts_with(['+', '-']));
+
+  match relative {
+    Some(rest) if rest.trim().is_empty() => {
+      Ok((ParameterMode::Relative, Expression { label: None, offset: 0 }))
+    },
+    Some(rest) => {
+      let rest = rest.trim_start();
+      let mut expression = parse_expression(line, &rest[1..])?;
+
+      if rest.starts_with('-') {
+        if expression.label.is_some() {
+          return fail(line, format!("can't negate label in '{}'", text));
+        }
+
+        expression.offset = match expression.offset.checked_neg() {
+          Some(offset) => offset,
+          None => return fail(line, format!("offset out of range in '{}'", text))
+        };
+      }
+
+      Ok((ParameterMode::Relative, expression))
+    },
+    None => Ok((ParameterMode::Position, parse_expression(line, inner)?))
+  }
+}
+
+fn parse_data_item(line: usize, text: &str) -> Result<DataItem, AsmError> {
+  match text.strip_prefix('"').and_then(|text| text.strip_suffix('"')) {
+    Some(string) => Ok(DataItem::Text(string.replace("\\n", "\n"))),
+    None => Ok(DataItem::Value(parse_expression(line, text)?))
+  }
+}
+
+fn parse_statement(
+  line: usize,
+  text: &str,
+  labels: &mut HashMap<String, i64>,
+  address: i64
+) -> Result<Option<Statement>, AsmError> {
+  let (label_text, text) = split_labels(text);
+
+  for label in label_text.split(':').map(|label| label.trim()).filter(|label| !label.is_empty()) {
+    if labels.insert(label.to_owned(), address).is_some() {
+      return fail(line, format!("duplicate label '{}'", label));
+    }
+  }
+
+  if text.is_empty() {
+    return Ok(None);
+  }
+
+  let (mnemonic, arguments) = split_mnemonic(text);
+
+  if mnemonic.to_lowercase() == "data" {
+    let items = split_arguments(arguments).iter()
+      .map(|item| parse_data_item(line, item))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    return Ok(Some(Statement::Data(items)));
+  }
+
+  let operation = match get_operation(mnemonic) {
+    Some(operation) => operation,
+    None => return fail(line, format!("unknown instruction '{}'", mnemonic))
+  };
+
+  let mut operands = match arguments.find("->") {
+    Some(i) => {
+      let mut operands = split_arguments(&arguments[..i]);
+      operands.push(arguments[i + 2..].trim().to_owned());
+      operands
+    },
+    None => split_arguments(arguments)
+  };
+
+  if operands.len() == 1 && operands[0].is_empty() {
+    operands.clear();
+  }
+
+  let operands = operands.iter()
+    .map(|operand| parse_operand(line, operand))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  if operands.len() != operation.parameter_count() {
+    return fail(line, format!(
+      "'{}' expects {} operands, got {}",
+      mnemonic, operation.parameter_count(), operands.len()
+    ));
+  }
+
+  if let Some(i) = operation.output_parameter() {
+    if operands[i].0 == ParameterMode::Immediate {
+      return fail(line, format!("'{}' can't write to an immediate operand", mnemonic));
+    }
+  }
+
+  Ok(Some(Statement::Instruction(operation, operands)))
+}
+
+fn get_statement_size(statement: &Statement) -> usize {
+  match statement {
+    Statement::Instruction(operation, _) => operation.parameter_count() + 1,
+    Statement::Data(items) => items.iter()
+      .map(|item| match item {
+        DataItem::Value(_) => 1,
+        DataItem::Text(text) => text.chars().count()
+      })
+      .sum()
+  }
+}
+
+/// Assembles Intcode assembly into a program.
+///
+/// Instructions are written as `add #1, [rb-2] -> [counter]`, with `#` for
+/// immediate, `[...]` for position and `[rb+...]` for relative parameters.
+/// Operands may refer to labels defined with `name:`, and `data` emits
+/// numbers, labels or `"strings"` verbatim. Macros are defined between
+/// `.macro name param, ...` and `.endm`, where `$param` is replaced by the
+/// argument and `$$` by a number unique to each expansion.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+  let mut labels = HashMap::new();
+  let mut statements = vec![];
+  let mut address = 0;
+
+  for (line, text) in expand_macros(source)? {
+    if let Some(statement) = parse_statement(line, &text, &mut labels, address as i64)? {
+      address += get_statement_size(&statement);
+      statements.push((line, statement));
+    }
+  }
+
+  let resolve = |line: usize, expression: &Expression| match &expression.label {
+    Some(label) => match labels.get(label) {
+      Some(value) => match value.checked_add(expression.offset) {
+        Some(value) => Ok(value),
+        None => fail(line, format!("offset of label '{}' out of range", label))
+      },
+      None => fail(line, format!("undefined label '{}'", label))
+    },
+    None => Ok(expression.offset)
+  };
+
+  let mut program = Vec::with_capacity(address);
+
+  for (line, statement) in statements {
+    match statement {
+      Statement::Instruction(operation, operands) => {
+        let modes = operands.iter()
+          .enumerate()
+          .map(|(i, (mode, _))| {
+            let digit = match mode {
+              ParameterMode::Position => 0,
+              ParameterMode::Immediate => 1,
+              ParameterMode::Relative => 2
+            };
+
+            digit * 10i64.pow(2 + i as u32)
+          })
+          .sum::<i64>();
+
+        program.push(operation.code() + modes);
+
+        for (_, expression) in &operands {
+          program.push(resolve(line, expression)?);
+        }
+      },
+      Statement::Data(items) => {
+        for item in items {
+          match item {
+            DataItem::Value(expression) => program.push(resolve(line, &expression)?),
+            DataItem::Text(text) => program.extend(text.chars().map(|c| c as i64))
+          }
+        }
+      }
+    }
+  }
+
+  Ok(program)
+}
+
+#[test]
+fn assemble_countdown() {
+  use crate::{run_program_with_inputs, Machine};
+
+  let source = "
+    .macro dec target
+      add $target, #-1 -> $target
+    .endm
+
+    ; Prints the numbers from the input down to 1
+          in -> [counter]
+    loop: out [counter]
+    next: dec [counter]
+          jt [counter], #loop
+          hlt
+    counter: data 0
+  ";
+
+  let program = assemble(source).unwrap();
+  let (outputs, _) = run_program_with_inputs(&mut Machine::new(program), std::iter::once(3)).unwrap();
+
+  assert_eq!(outputs, vec![3, 2, 1]);
+}
+
+#[test]
+fn assemble_disassembly() {
+  use crate::disasm::{disassemble, ListingEntry};
+
+  let program = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99, 5, 6];
+  let source = disassemble(&program).entries.iter()
+    .map(|entry| match entry {
+      ListingEntry::Code { instruction, .. } => instruction.to_string(),
+      ListingEntry::Data { values, .. } => {
+        let values = values.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        format!("DATA {}", values.join(", "))
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+
+  assert_eq!(assemble(&source), Ok(program));
+}
+
+#[test]
+fn assemble_errors() {
+  assert_eq!(assemble("jt #1, #nowhere").unwrap_err().line, 1);
+  assert_eq!(assemble("hlt\nadd #1, #2 -> #3").unwrap_err().line, 2);
+  assert_eq!(assemble("x: hlt\nx: hlt").unwrap_err().line, 2);
+  assert_eq!(assemble(".macro m a\nout $a").unwrap_err().line, 1);
+  assert_eq!(assemble("jmp #0").unwrap_err().line, 1);
+  assert_eq!(assemble("hlt\nout #x+9223372036854775807\nx: hlt").unwrap_err(), AsmError { line: 2, message: "offset of label 'x' out of range".to_owned() });
+  assert!(assemble("out [rb - -9223372036854775808]").unwrap_err().message.contains("out of range"));
+}
@@ -0,0 +1,113 @@
+use std::{env, fs};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use intcode::{parse_program, run_program_with_inputs, FastMachine, Machine, VmError};
+
+type Engine = fn(&[i64], &[i64]) -> Result<Vec<i64>, VmError>;
+type Run = (Vec<i64>, Vec<i64>);
+
+struct Workload {
+  name: &'static str,
+  file: &'static str,
+  runs: fn(&[i64]) -> Vec<Run>
+}
+
+fn patched(program: &[i64], patches: &[(usize, i64)]) -> Vec<i64> {
+  let mut program = program.to_vec();
+
+  for &(address, value) in patches {
+    program[address] = value;
+  }
+
+  program
+}
+
+const WORKLOADS: &[Workload] = &[
+  Workload {
+    name: "02 noun/verb search",
+    file: "02.txt",
+    runs: |program| (0..100)
+      .flat_map(|noun| (0..100).map(move |verb| (noun, verb)))
+      .map(|(noun, verb)| (patched(program, &[(1, noun), (2, verb)]), vec![]))
+      .collect()
+  },
+  Workload {
+    name: "05 diagnostics",
+    file: "05.txt",
+    runs: |program| vec![(program.to_vec(), vec![1]), (program.to_vec(), vec![5])]
+  },
+  Workload {
+    name: "09 sensor boost",
+    file: "09.txt",
+    runs: |program| vec![(program.to_vec(), vec![2])]
+  },
+  Workload {
+    name: "19 50x50 beam scan",
+    file: "19.txt",
+    runs: |program| (0..50)
+      .flat_map(|y| (0..50).map(move |x| (x, y)))
+      .map(|(x, y)| (program.to_vec(), vec![x, y]))
+      .collect()
+  }
+];
+
+const ENGINES: &[(&str, Engine)] = &[
+  ("interpreter", |program, inputs| {
+    let mut machine = Machine::new(program.to_vec());
+    run_program_with_inputs(&mut machine, inputs.iter().cloned()).map(|(outputs, _)| outputs)
+  }),
+  ("fast, paged", |program, inputs| {
+    let mut machine = FastMachine::new(program.to_vec());
+    machine.run_with_inputs(inputs.iter().cloned()).map(|(outputs, _)| outputs)
+  }),
+  ("fast, dense", |program, inputs| {
+    let mut machine = FastMachine::with_memory(program.to_vec());
+    machine.run_with_inputs(inputs.iter().cloned()).map(|(outputs, _)| outputs)
+  })
+];
+
+fn measure(engine: Engine, runs: &[Run]) -> Result<(Duration, Vec<Vec<i64>>), VmError> {
+  let start = Instant::now();
+  let outputs = runs.iter()
+    .map(|(program, inputs)| engine(program, inputs))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  Ok((start.elapsed(), outputs))
+}
+
+fn main() {
+  let directory = env::args().nth(1).unwrap_or_else(|| ".".to_owned());
+
+  for workload in WORKLOADS {
+    let path = Path::new(&directory).join(workload.file);
+    let program = match fs::read_to_string(&path) {
+      Ok(input) => parse_program(&input),
+      Err(err) => {
+        println!("{}: skipped, {}: {}", workload.name, path.display(), err);
+        continue;
+      }
+    };
+
+    let runs = (workload.runs)(&program);
+    let mut expected = None;
+
+    println!("{} ({} runs)", workload.name, runs.len());
+
+    for &(name, engine) in ENGINES {
+      match measure(engine, &runs) {
+        Ok((duration, outputs)) => {
+          let verdict = match &expected {
+            None => "",
+            Some(expected) if *expected == outputs => "",
+            Some(_) => "  MISMATCH"
+          };
+
+          println!("  {:<12}{:>10.2?}{}", name, duration, verdict);
+          expected.get_or_insert(outputs);
+        },
+        Err(err) => println!("  {:<12}Failure: {}", name, err)
+      }
+    }
+  }
+}
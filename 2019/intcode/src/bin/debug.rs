@@ -0,0 +1,153 @@
+use std::{env, fs, io};
+use std::io::prelude::*;
+
+use intcode::{parse_program, Debugger, Machine, StopReason, VmError};
+
+const HELP: &str = "\
+step [n]            execute n instructions (s)
+continue            run until a breakpoint, watchpoint, input or halt (c)
+break <addr>        set a breakpoint (b)
+delete <addr>       remove a breakpoint (d)
+watch <addr>        stop when a memory cell changes (w)
+unwatch <addr>      remove a watchpoint
+input <x>...        queue numbers as input (i)
+ascii <text>        queue text followed by a newline as input
+history [n]         show the last n executed instructions (bt)
+regs                show instruction pointer and relative base (r)
+mem <addr> [len]    dump memory (m)
+list                show the next instruction (l)
+quit                exit the debugger (q)";
+
+fn print_stop(debugger: &mut Debugger, result: Result<StopReason, VmError>) {
+  if !debugger.outputs.is_empty() {
+    let outputs = debugger.outputs.drain(..)
+      .map(|x| x.to_string())
+      .collect::<Vec<_>>();
+
+    println!("output: {}", outputs.join(", "));
+  }
+
+  match result {
+    Ok(StopReason::Stepped) => {},
+    Ok(StopReason::Breakpoint(address)) => println!("breakpoint at {}", address),
+    Ok(StopReason::Watchpoint { address, old, new }) => {
+      println!("watchpoint at {}: {} -> {}", address, old, new);
+    },
+    Ok(StopReason::WaitForInput) => println!("waiting for input"),
+    Ok(StopReason::Halt) => println!("halted"),
+    Err(err) => println!("error: {}", err)
+  }
+
+  match debugger.current_instruction() {
+    Ok(instruction) => println!("{:>6}  {}", debugger.machine.instruction_pointer, instruction),
+    Err(_) => println!("{:>6}  ???", debugger.machine.instruction_pointer)
+  }
+}
+
+fn parse_args<T: std::str::FromStr>(args: &[&str]) -> Option<Vec<T>> {
+  args.iter().map(|arg| arg.parse::<T>().ok()).collect()
+}
+
+fn main() {
+  let path = match env::args().nth(1) {
+    Some(path) => path,
+    None => {
+      eprintln!("Usage: debug <program>");
+      return;
+    }
+  };
+
+  let program = match fs::read_to_string(&path) {
+    Ok(input) => parse_program(&input),
+    Err(err) => {
+      eprintln!("Failure: {}", err);
+      return;
+    }
+  };
+
+  let mut debugger = Debugger::new(Machine::new(program));
+  let stdin = io::stdin();
+
+  print!("> ");
+  io::stdout().flush().unwrap();
+
+  for line in stdin.lock().lines() {
+    let line = line.unwrap();
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+    match (tokens.first().cloned().unwrap_or(""), &tokens[tokens.len().min(1)..]) {
+      ("", _) => {},
+      ("s", args) | ("step", args) => {
+        let count = parse_args::<usize>(args).and_then(|args| args.first().cloned()).unwrap_or(1);
+        let mut result = Ok(StopReason::Stepped);
+
+        for _ in 0..count {
+          result = debugger.step();
+
+          if result != Ok(StopReason::Stepped) {
+            break;
+          }
+        }
+
+        print_stop(&mut debugger, result);
+      },
+      ("c", _) | ("continue", _) => {
+        let result = debugger.run();
+        print_stop(&mut debugger, result);
+      },
+      ("b", [address]) | ("break", [address]) => match address.parse() {
+        Ok(address) => {
+          debugger.add_breakpoint(address);
+        },
+        Err(_) => println!("invalid address")
+      },
+      ("d", [address]) | ("delete", [address]) => match address.parse() {
+        Ok(address) => {
+          debugger.remove_breakpoint(address);
+        },
+        Err(_) => println!("invalid address")
+      },
+      ("w", [address]) | ("watch", [address]) => match address.parse() {
+        Ok(address) => {
+          debugger.add_watchpoint(address);
+        },
+        Err(_) => println!("invalid address")
+      },
+      ("unwatch", [address]) => match address.parse() {
+        Ok(address) => {
+          debugger.remove_watchpoint(address);
+        },
+        Err(_) => println!("invalid address")
+      },
+      ("i", args) | ("input", args) => match parse_args::<i64>(args) {
+        Some(values) => debugger.inputs.extend(values),
+        None => println!("invalid input")
+      },
+      ("ascii", _) => {
+        let text = line.trim_start()["ascii".len()..].trim();
+        debugger.inputs.extend(text.chars().chain(Some('\n')).map(|c| c as i64));
+      },
+      ("bt", args) | ("history", args) => {
+        let count = parse_args::<usize>(args).and_then(|args| args.first().cloned()).unwrap_or(10);
+        let entries = debugger.history().take(count).collect::<Vec<_>>();
+
+        for entry in entries.into_iter().rev() {
+          println!("{}", entry);
+        }
+      },
+      ("r", _) | ("regs", _) => println!("{}", debugger.dump_registers()),
+      ("m", args) | ("mem", args) => match parse_args::<usize>(args).as_deref() {
+        Some([address]) => print!("{}", debugger.dump_memory(*address, 8)),
+        Some([address, len]) => print!("{}", debugger.dump_memory(*address, *len)),
+        _ => println!("usage: mem <addr> [len]")
+      },
+      ("l", _) | ("list", _) => print_stop(&mut debugger, Ok(StopReason::Stepped)),
+      ("q", _) | ("quit", _) => break,
+      ("help", _) => println!("{}", HELP),
+      _ => println!("unknown command, try 'help'")
+    }
+
+    print!("> ");
+    io::stdout().flush().unwrap();
+  }
+}
@@ -0,0 +1,19 @@
+use std::{env, fs, io};
+use std::io::prelude::*;
+
+use intcode::{disassemble, parse_program};
+
+fn main() {
+  let input = match env::args().nth(1) {
+    Some(path) => fs::read_to_string(path),
+    None => {
+      let mut contents = String::new();
+      io::stdin().read_to_string(&mut contents).map(|_| contents)
+    }
+  };
+
+  match input {
+    Ok(input) => print!("{}", disassemble(&parse_program(&input))),
+    Err(err) => eprintln!("Failure: {}", err)
+  }
+}
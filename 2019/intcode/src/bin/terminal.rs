@@ -0,0 +1,93 @@
+use std::{env, fs, io};
+
+use intcode::{parse_program, replay, Machine, ProgramResult, Terminal, Transcript};
+
+const USAGE: &str = "Usage: terminal <program> [--script <file>] [--record <file>] [--replay <file>]";
+
+fn main() {
+  let mut args = env::args().skip(1);
+  let mut path = None;
+  let mut script = None;
+  let mut record = None;
+  let mut transcript = None;
+
+  while let Some(arg) = args.next() {
+    let target = match arg.as_str() {
+      "--script" => &mut script,
+      "--record" => &mut record,
+      "--replay" => &mut transcript,
+      _ => {
+        path = Some(arg);
+        continue;
+      }
+    };
+
+    *target = args.next();
+
+    if target.is_none() {
+      eprintln!("{}", USAGE);
+      return;
+    }
+  }
+
+  let program = match path.map(fs::read_to_string) {
+    Some(Ok(input)) => parse_program(&input),
+    Some(Err(err)) => {
+      eprintln!("Failure: {}", err);
+      return;
+    },
+    None => {
+      eprintln!("{}", USAGE);
+      return;
+    }
+  };
+
+  if let Some(transcript) = transcript {
+    let expected = match Transcript::load(&transcript) {
+      Ok(expected) => expected,
+      Err(err) => {
+        eprintln!("Failure: {}: {}", transcript, err);
+        return;
+      }
+    };
+
+    match replay(Machine::new(program), &expected) {
+      Ok(actual) if actual == expected => println!("Replay matches {}", transcript),
+      Ok(actual) => {
+        let index = expected.entries.iter().zip(&actual.entries)
+          .position(|(x, y)| x != y)
+          .unwrap_or_else(|| expected.entries.len().min(actual.entries.len()));
+
+        println!("Replay diverges from {} at entry {}", transcript, index + 1);
+        println!("expected: {:?}", expected.entries.get(index));
+        println!("actual:   {:?}", actual.entries.get(index));
+      },
+      Err(err) => println!("Failure: {}", err)
+    }
+
+    return;
+  }
+
+  let mut terminal = Terminal::new(Machine::new(program));
+
+  if let Some(script) = script {
+    if let Err(err) = terminal.load_script(&script) {
+      eprintln!("Failure: {}: {}", script, err);
+      return;
+    }
+  }
+
+  let stdin = io::stdin();
+
+  match terminal.run(stdin.lock(), io::stdout()) {
+    Ok(ProgramResult::Halt) => {},
+    Ok(_) => println!("\n(end of input)"),
+    Err(err) => println!("Failure: {}", err)
+  }
+
+  if let Some(record) = record {
+    if let Err(err) = terminal.transcript.save(&record) {
+      eprintln!("Failure: {}: {}", record, err);
+    }
+  }
+}
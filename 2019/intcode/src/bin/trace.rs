@@ -0,0 +1,41 @@
+use std::{env, fs};
+
+use intcode::{parse_program, Machine, Tracer};
+
+fn main() {
+  let args = env::args().skip(1).collect::<Vec<_>>();
+  let record_trace = args.iter().any(|arg| arg == "--trace");
+  let args = args.into_iter().filter(|arg| arg != "--trace").collect::<Vec<_>>();
+
+  let path = match args.first() {
+    Some(path) => path,
+    None => {
+      eprintln!("Usage: trace [--trace] <program> [inputs...]");
+      return;
+    }
+  };
+
+  let program = match fs::read_to_string(path) {
+    Ok(input) => parse_program(&input),
+    Err(err) => {
+      eprintln!("Failure: {}", err);
+      return;
+    }
+  };
+
+  let inputs = args[1..].iter()
+    .flat_map(|arg| parse_program(arg))
+    .collect::<Vec<_>>();
+
+  let mut tracer = Tracer::new(record_trace);
+  let result = tracer.run_program_with_inputs(&mut Machine::new(program), inputs.into_iter());
+
+  print!("{}", tracer.trace());
+
+  match result {
+    Ok((outputs, result)) => println!("outputs: {:?}\nresult: {:?}", outputs, result),
+    Err(err) => println!("Failure: {}", err)
+  }
+
+  print!("{}", tracer.profile);
+}
@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::io::{Input, Output};
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::Memory;
+use crate::network::{NetworkEvent, Packet};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Every NIC counts the empty polls since it last sent or received a
+/// packet. The bus is idle once no packet is in flight and every running
+/// NIC polled an empty queue at least twice, and then polled once more
+/// without any count being reset, so no NIC did anything in between.
+struct Shared {
+  empty_polls: Vec<AtomicUsize>,
+  stopped: Vec<AtomicBool>,
+  in_flight: AtomicUsize,
+  shutdown: AtomicBool
+}
+
+impl Shared {
+  fn running_polls(&self) -> Vec<Option<usize>> {
+    self.empty_polls.iter().zip(&self.stopped)
+      .map(|(polls, stopped)| match stopped.load(Ordering::SeqCst) {
+        true => None,
+        false => Some(polls.load(Ordering::SeqCst))
+      })
+      .collect()
+  }
+
+  fn is_finished(&self) -> bool {
+    self.shutdown.load(Ordering::SeqCst)
+      || self.stopped.iter().all(|stopped| stopped.load(Ordering::SeqCst))
+  }
+}
+
+/// Poll counts at the moment the bus looked idle, see `Shared`.
+enum IdleCheck {
+  Busy,
+  Candidate(Vec<Option<usize>>)
+}
+
+impl IdleCheck {
+  /// Advances the check and returns `true` once the bus is idle.
+  fn update(&mut self, shared: &Shared) -> bool {
+    let in_flight = shared.in_flight.load(Ordering::SeqCst);
+    let polls = shared.running_polls();
+
+    match self {
+      IdleCheck::Busy => {
+        if in_flight == 0 && polls.iter().all(|polls| polls.is_none_or(|n| n >= 2)) {
+          *self = IdleCheck::Candidate(polls);
+        }
+
+        false
+      },
+      IdleCheck::Candidate(snapshot) => {
+        let pairs = snapshot.iter().zip(&polls);
+
+        if in_flight > 0 || pairs.clone().any(|pair| matches!(pair, (Some(a), Some(b)) if b < a)) {
+          *self = IdleCheck::Busy;
+          false
+        } else if pairs.clone().all(|pair| matches!(pair, (Some(a), Some(b)) if b > a) || pair.1.is_none()) {
+          *self = IdleCheck::Busy;
+          true
+        } else {
+          false
+        }
+      }
+    }
+  }
+}
+
+struct NicInput {
+  id: usize,
+  pending: VecDeque<i64>,
+  packets: Receiver<Packet>,
+  shared: Arc<Shared>
+}
+
+impl Input for NicInput {
+  fn next_input(&mut self) -> Option<i64> {
+    if let Some(value) = self.pending.pop_front() {
+      return Some(value);
+    }
+
+    let shared = &self.shared;
+
+    if shared.shutdown.load(Ordering::SeqCst) {
+      return None;
+    }
+
+    let empty_polls = &shared.empty_polls[self.id];
+    let packet = match empty_polls.load(Ordering::SeqCst) {
+      0 | 1 => self.packets.try_recv().ok(),
+      _ => self.packets.recv_timeout(POLL_INTERVAL).ok()
+    };
+
+    match packet {
+      Some(packet) => {
+        empty_polls.store(0, Ordering::SeqCst);
+        shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        self.pending.push_back(packet.y);
+        Some(packet.x)
+      },
+      None => {
+        empty_polls.fetch_add(1, Ordering::SeqCst);
+        Some(-1)
+      }
+    }
+  }
+}
+
+struct NicOutput {
+  id: usize,
+  buffer: Vec<i64>,
+  nics: Vec<Sender<Packet>>,
+  nat: Option<(usize, Sender<Packet>)>,
+  events: Sender<NetworkEvent>,
+  shared: Arc<Shared>
+}
+
+impl NicOutput {
+  fn deliver(&self, sender: &Sender<Packet>, packet: Packet) {
+    self.shared.in_flight.fetch_add(1, Ordering::SeqCst);
+
+    if sender.send(packet).is_err() {
+      self.shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+  }
+}
+
+impl Output for NicOutput {
+  fn write_output(&mut self, value: i64) {
+    self.buffer.push(value);
+
+    if self.buffer.len() < 3 {
+      return;
+    }
+
+    let packet = Packet { from: self.id, to: self.buffer[0] as usize, x: self.buffer[1], y: self.buffer[2] };
+    let shared = &self.shared;
+
+    self.buffer.clear();
+    shared.empty_polls[self.id].store(0, Ordering::SeqCst);
+
+    if self.events.send(NetworkEvent::Packet(packet)).is_err() {
+      shared.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    match &self.nat {
+      Some((address, nat)) if *address == packet.to => self.deliver(nat, packet),
+      _ => if let Some(nic) = self.nics.get(packet.to) {
+        self.deliver(nic, packet);
+      }
+    }
+  }
+}
+
+/// Runs every NIC of a bus on its own thread. A coordinator thread plays the
+/// NAT and reports idle rounds. All threads shut down once every NIC has
+/// stopped or the returned receiver is dropped.
+pub(crate) fn run_bus_threaded<M>(
+  machines: Vec<Machine<M>>,
+  inputs: Vec<VecDeque<i64>>,
+  nat: Option<usize>
+) -> Receiver<NetworkEvent>
+where M: Memory + Send + 'static {
+  let count = machines.len();
+  let (events, event_receiver) = channel();
+  let (nat_sender, nat_receiver) = channel();
+  let (nics, receivers): (Vec<_>, Vec<_>) = (0..count).map(|_| channel()).unzip();
+
+  let shared = Arc::new(Shared {
+    empty_polls: (0..count).map(|_| AtomicUsize::new(0)).collect(),
+    stopped: (0..count).map(|_| AtomicBool::new(false)).collect(),
+    in_flight: AtomicUsize::new(0),
+    shutdown: AtomicBool::new(false)
+  });
+
+  let nic_threads = machines.into_iter().zip(receivers).zip(inputs).enumerate()
+    .map(|(id, ((mut machine, packets), pending))| {
+      let mut input = NicInput { id, pending, packets, shared: shared.clone() };
+      let mut output = NicOutput {
+        id,
+        buffer: vec![],
+        nics: nics.clone(),
+        nat: nat.map(|address| (address, nat_sender.clone())),
+        events: events.clone(),
+        shared: shared.clone()
+      };
+
+      thread::spawn(move || {
+        let result = machine.run_with_io(&mut input, &mut output);
+
+        output.shared.stopped[id].store(true, Ordering::SeqCst);
+
+        match result {
+          Ok(ProgramResult::Halt) => output.events.send(NetworkEvent::Halt(id)).ok(),
+          Err(err) => output.events.send(NetworkEvent::Crash(id, err)).ok(),
+          _ => None
+        };
+      })
+    })
+    .collect::<Vec<_>>();
+
+  drop(nat_sender);
+
+  thread::spawn(move || {
+    let mut held = None;
+    let mut idle_check = IdleCheck::Busy;
+
+    while !shared.is_finished() {
+      if let Ok(packet) = nat_receiver.recv_timeout(POLL_INTERVAL) {
+        held = Some(packet);
+        shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+        continue;
+      }
+
+      if !idle_check.update(&shared) {
+        continue;
+      }
+
+      if events.send(NetworkEvent::Idle).is_err() {
+        break;
+      }
+
+      if let (Some(address), Some(packet), Some(first)) = (nat, held.take(), nics.first()) {
+        let packet = Packet { from: address, to: 0, ..packet };
+
+        shared.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        if events.send(NetworkEvent::Packet(packet)).is_err() || first.send(packet).is_err() {
+          break;
+        }
+      }
+    }
+
+    shared.shutdown.store(true, Ordering::SeqCst);
+
+    for thread in nic_threads {
+      thread.join().ok();
+    }
+  });
+
+  event_receiver
+}
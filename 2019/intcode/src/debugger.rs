@@ -0,0 +1,224 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+use crate::error::VmError;
+use crate::instruction::Instruction;
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::Memory;
+
+pub const DEFAULT_HISTORY_SIZE: usize = 64;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum StopReason {
+  Stepped,
+  Breakpoint(usize),
+  Watchpoint { address: usize, old: i64, new: i64 },
+  WaitForInput,
+  Halt
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+  pub address: usize,
+  pub relative_base: i64,
+  pub instruction: Instruction
+}
+
+impl fmt::Display for HistoryEntry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:>6}  {:<32}rb={}", self.address, self.instruction.to_string(), self.relative_base)
+  }
+}
+
+pub struct Debugger {
+  pub machine: Machine,
+  pub inputs: VecDeque<i64>,
+  pub outputs: Vec<i64>,
+  breakpoints: BTreeSet<usize>,
+  watchpoints: BTreeSet<usize>,
+  history: VecDeque<HistoryEntry>,
+  history_size: usize
+}
+
+impl Debugger {
+  pub fn new(machine: Machine) -> Debugger {
+    Debugger {
+      machine,
+      inputs: VecDeque::new(),
+      outputs: vec![],
+      breakpoints: BTreeSet::new(),
+      watchpoints: BTreeSet::new(),
+      history: VecDeque::new(),
+      history_size: DEFAULT_HISTORY_SIZE
+    }
+  }
+
+  pub fn set_history_size(&mut self, size: usize) {
+    self.history_size = size;
+    self.history.truncate(size);
+  }
+
+  pub fn add_breakpoint(&mut self, address: usize) -> bool {
+    self.breakpoints.insert(address)
+  }
+
+  pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+    self.breakpoints.remove(&address)
+  }
+
+  pub fn breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+    self.breakpoints.iter().cloned()
+  }
+
+  pub fn add_watchpoint(&mut self, address: usize) -> bool {
+    self.watchpoints.insert(address)
+  }
+
+  pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+    self.watchpoints.remove(&address)
+  }
+
+  pub fn watchpoints(&self) -> impl Iterator<Item = usize> + '_ {
+    self.watchpoints.iter().cloned()
+  }
+
+  /// Most recently executed instructions, latest first.
+  pub fn history(&self) -> impl Iterator<Item = &HistoryEntry> {
+    self.history.iter()
+  }
+
+  pub fn read(&self, address: usize) -> i64 {
+    self.machine.memory.read(address)
+  }
+
+  pub fn current_instruction(&self) -> Result<Instruction, VmError> {
+    self.machine.instruction_at(self.machine.instruction_pointer)
+  }
+
+  pub fn step(&mut self) -> Result<StopReason, VmError> {
+    let address = self.machine.instruction_pointer;
+    let relative_base = self.machine.relative_base;
+    let watched = self.watchpoints.iter()
+      .map(|&address| (address, self.read(address)))
+      .collect::<Vec<_>>();
+
+    let mut input = self.inputs.front().cloned();
+    let had_input = input.is_some();
+    let instruction = self.current_instruction().ok();
+    let result = self.machine.step(&mut input)?;
+
+    if had_input && input.is_none() {
+      self.inputs.pop_front();
+    }
+
+    match result {
+      Some(ProgramResult::WaitForInput) => return Ok(StopReason::WaitForInput),
+      Some(ProgramResult::Halt) => return Ok(StopReason::Halt),
+      Some(ProgramResult::Output(x)) => self.outputs.push(x),
+      None => {}
+    }
+
+    if let Some(instruction) = instruction {
+      self.history.push_front(HistoryEntry { address, relative_base, instruction });
+      self.history.truncate(self.history_size);
+    }
+
+    let changed = watched.into_iter()
+      .map(|(address, old)| (address, old, self.read(address)))
+      .find(|&(_, old, new)| old != new);
+
+    Ok(match changed {
+      Some((address, old, new)) => StopReason::Watchpoint { address, old, new },
+      None => StopReason::Stepped
+    })
+  }
+
+  /// Runs until a breakpoint or watchpoint is hit, the machine needs input
+  /// or halts. A breakpoint at the current instruction doesn't stop it, so
+  /// calling this repeatedly continues past the breakpoint.
+  pub fn run(&mut self) -> Result<StopReason, VmError> {
+    let mut first = true;
+
+    loop {
+      let address = self.machine.instruction_pointer;
+
+      if !first && self.breakpoints.contains(&address) {
+        return Ok(StopReason::Breakpoint(address));
+      }
+
+      first = false;
+
+      match self.step()? {
+        StopReason::Stepped => {},
+        reason => return Ok(reason)
+      }
+    }
+  }
+
+  pub fn dump_registers(&self) -> String {
+    format!(
+      "ip={} rb={} memory={}",
+      self.machine.instruction_pointer,
+      self.machine.relative_base,
+      self.machine.memory.len()
+    )
+  }
+
+  pub fn dump_memory(&self, start: usize, len: usize) -> String {
+    let mut result = String::new();
+
+    let end = start.saturating_add(len);
+
+    for row in (start..end).step_by(8) {
+      let values = (row..row.saturating_add(8).min(end))
+        .map(|address| format!("{:>8}", self.read(address)))
+        .collect::<String>();
+
+      result.push_str(&format!("{:>6}:{}\n", row, values));
+    }
+
+    result
+  }
+}
+
+#[test]
+fn breakpoints_and_watchpoints() {
+  use crate::assemble;
+
+  let program = assemble("
+          in -> [x]
+    loop: mul [x], #2 -> [x]
+          out [x]
+          lt [x], #20 -> [cond]
+          jt [cond], #loop
+          hlt
+    x:    data 0
+    cond: data 0
+  ").unwrap();
+
+  let loop_address = 2;
+  let x_address = program.len() - 2;
+  let mut debugger = Debugger::new(Machine::new(program));
+
+  assert_eq!(debugger.run(), Ok(StopReason::WaitForInput));
+
+  debugger.inputs.push_back(3);
+  debugger.add_breakpoint(loop_address);
+
+  assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(loop_address)));
+  assert_eq!(debugger.read(x_address), 3);
+
+  debugger.add_watchpoint(x_address);
+
+  assert_eq!(debugger.run(), Ok(StopReason::Watchpoint { address: x_address, old: 3, new: 6 }));
+
+  debugger.remove_watchpoint(x_address);
+  debugger.remove_breakpoint(loop_address);
+
+  assert_eq!(debugger.run(), Ok(StopReason::Halt));
+  assert_eq!(debugger.outputs, vec![6, 12, 24]);
+  assert_eq!(debugger.history().next().map(|entry| entry.address), Some(12));
+
+  assert_eq!(debugger.dump_memory(x_address, 2), format!("{:>6}:{:>8}{:>8}\n", x_address, 24, 0));
+  assert_eq!(debugger.dump_memory(usize::MAX - 1, 8), format!("{:>6}:{:>8}\n", usize::MAX - 1, 0));
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use crate::instruction::{parse_instruction, Instruction, OperationType, ParameterMode};
+
+const DATA_VALUES_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListingEntry {
+  Code {
+    address: usize,
+    instruction: Instruction,
+    jump_sources: Vec<usize>,
+    return_target: bool
+  },
+  Data {
+    address: usize,
+    values: Vec<i64>
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listing {
+  pub entries: Vec<ListingEntry>
+}
+
+fn get_stored_constant(instruction: &Instruction) -> Option<i64> {
+  match (instruction.operation, instruction.inputs.as_slice()) {
+    (OperationType::Add, [(ParameterMode::Immediate, a), (ParameterMode::Immediate, b), _]) => a.checked_add(*b),
+    (OperationType::Multiply, [(ParameterMode::Immediate, a), (ParameterMode::Immediate, b), _]) => a.checked_mul(*b),
+    _ => None
+  }
+}
+
+/// Recursively follows the control flow from address 0 to tell code from
+/// data. Jumps with a non-immediate target can't be followed statically, but
+/// the code after them is still picked up if its address gets stored as a
+/// constant somewhere, which is how Intcode programs push return addresses.
+pub fn disassemble(program: &[i64]) -> Listing {
+  let mut code = BTreeMap::<usize, Instruction>::new();
+  let mut jump_sources = BTreeMap::<usize, Vec<usize>>::new();
+  let mut return_targets = BTreeSet::new();
+  let mut stored_constants = HashSet::new();
+  let mut dead_ends = BTreeSet::new();
+  let mut stack = vec![0];
+
+  loop {
+    while let Some(address) = stack.pop() {
+      if address >= program.len() || code.contains_key(&address) {
+        continue;
+      }
+
+      let instruction = match parse_instruction(program, address) {
+        Ok(instruction) => instruction,
+        Err(_) => continue
+      };
+
+      let next = address + instruction.inputs.len() + 1;
+
+      if next > program.len() {
+        continue;
+      }
+
+      if let Some(value) = get_stored_constant(&instruction) {
+        stored_constants.insert(value);
+      }
+
+      match instruction.operation {
+        OperationType::Halt => {},
+        OperationType::JumpIfTrue | OperationType::JumpIfFalse => {
+          let jump_if = instruction.operation == OperationType::JumpIfTrue;
+          let (always, never) = match instruction.inputs[0] {
+            (ParameterMode::Immediate, x) => ((x != 0) == jump_if, (x != 0) != jump_if),
+            _ => (false, false)
+          };
+
+          if let (false, (ParameterMode::Immediate, target)) = (never, instruction.inputs[1]) {
+            if target >= 0 {
+              jump_sources.entry(target as usize).or_default().push(address);
+              stack.push(target as usize);
+            }
+          }
+
+          if always {
+            dead_ends.insert(next);
+          } else {
+            stack.push(next);
+          }
+        },
+        _ => stack.push(next)
+      }
+
+      code.insert(address, instruction);
+    }
+
+    let new_return_targets = dead_ends.iter()
+      .cloned()
+      .filter(|&address| stored_constants.contains(&(address as i64)))
+      .filter(|address| !return_targets.contains(address))
+      .collect::<Vec<_>>();
+
+    if new_return_targets.is_empty() {
+      break;
+    }
+
+    for address in new_return_targets {
+      return_targets.insert(address);
+      stack.push(address);
+    }
+  }
+
+  let mut entries = vec![];
+  let mut address = 0;
+
+  while address < program.len() {
+    match code.remove(&address) {
+      Some(instruction) => {
+        let size = instruction.inputs.len() + 1;
+
+        entries.push(ListingEntry::Code {
+          address,
+          instruction,
+          jump_sources: jump_sources.remove(&address).unwrap_or_default(),
+          return_target: return_targets.contains(&address)
+        });
+
+        address += size;
+      },
+      None => {
+        if let Some(ListingEntry::Data { address: start, values }) = entries.last_mut() {
+          if *start + values.len() == address && values.len() < DATA_VALUES_PER_LINE {
+            values.push(program[address]);
+            address += 1;
+            continue;
+          }
+        }
+
+        entries.push(ListingEntry::Data {
+          address,
+          values: vec![program[address]]
+        });
+
+        address += 1;
+      }
+    }
+  }
+
+  Listing { entries }
+}
+
+impl fmt::Display for Listing {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in &self.entries {
+      match entry {
+        ListingEntry::Code { address, instruction, jump_sources, return_target } => {
+          let mut comments = vec![];
+
+          if !jump_sources.is_empty() {
+            let sources = jump_sources.iter()
+              .map(|source| source.to_string())
+              .collect::<Vec<_>>();
+
+            comments.push(format!("from {}", sources.join(", ")));
+          }
+
+          if *return_target {
+            comments.push("return".to_owned());
+          }
+
+          let line = format!("{:>6}  {}", address, instruction);
+
+          if comments.is_empty() {
+            writeln!(f, "{}", line)?;
+          } else {
+            writeln!(f, "{:<40}; {}", line, comments.join(", "))?;
+          }
+        },
+        ListingEntry::Data { address, values } => {
+          let values = values.iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>();
+
+          writeln!(f, "{:>6}  DATA {}", address, values.join(", "))?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[test]
+fn disassemble_call() {
+  let program = vec![
+    21101, 7, 0, 0,  // push return address
+    1105, 1, 13,     // call
+    104, 1, 99,      // print, halt
+    7, 7, 7,         // data
+    204, 1,          // print
+    2105, 1, 0       // return
+  ];
+
+  let listing = disassemble(&program).to_string();
+  let expected = [
+    "     0  ADD #7, #0 -> [rb+0]",
+    "     4  JT #1, #13",
+    "     7  OUT #1                          ; return",
+    "     9  HLT",
+    "    10  DATA 7, 7, 7",
+    "    13  OUT [rb+1]                      ; from 4",
+    "    15  JT #1, [rb+0]",
+    ""
+  ].join("\n");
+
+  assert_eq!(listing, expected);
+}
+
+#[test]
+fn disassemble_overflowing_constants() {
+  let program = vec![1101, i64::MAX, 1, 0, 1102, i64::MIN, -1, 0, 99];
+  let listing = disassemble(&program);
+
+  assert_eq!(listing.entries.len(), 3);
+}
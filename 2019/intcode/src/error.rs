@@ -0,0 +1,39 @@
+use std::{error, fmt};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum VmError {
+  InvalidOpcode { pc: usize, code: i64 },
+  InvalidParameterMode { pc: usize, code: i64 },
+  NegativeAddress { pc: usize, address: i64 },
+  WriteToImmediate { pc: usize },
+  MemoryLimitExceeded { pc: usize, address: usize },
+  /// Arithmetic or address computation doesn't fit into an `i64`.
+  Overflow { pc: usize }
+}
+
+impl fmt::Display for VmError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      VmError::InvalidOpcode { pc, code } => {
+        write!(f, "unsupported operation code {} at {}", code, pc)
+      },
+      VmError::InvalidParameterMode { pc, code } => {
+        write!(f, "invalid parameter mode in instruction {} at {}", code, pc)
+      },
+      VmError::NegativeAddress { pc, address } => {
+        write!(f, "access to negative address {} at {}", address, pc)
+      },
+      VmError::WriteToImmediate { pc } => {
+        write!(f, "write to immediate parameter at {}", pc)
+      },
+      VmError::MemoryLimitExceeded { pc, address } => {
+        write!(f, "access to address {} beyond memory limit at {}", address, pc)
+      },
+      VmError::Overflow { pc } => {
+        write!(f, "integer overflow at {}", pc)
+      }
+    }
+  }
+}
+
+impl error::Error for VmError {}
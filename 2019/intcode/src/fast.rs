@@ -0,0 +1,333 @@
+use crate::error::VmError;
+use crate::instruction::OperationType;
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::{Memory, PagedMemory};
+use crate::io::{run_with_inputs, run_with_io, Input, Output};
+
+const MAX_INSTRUCTION_SIZE: usize = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Parameter {
+  Position(i64),
+  Immediate(i64),
+  Relative(i64)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Op {
+  Add(Parameter, Parameter, Parameter),
+  Multiply(Parameter, Parameter, Parameter),
+  Input(Parameter),
+  Output(Parameter),
+  JumpIfTrue(Parameter, Parameter),
+  JumpIfFalse(Parameter, Parameter),
+  LessThan(Parameter, Parameter, Parameter),
+  Equals(Parameter, Parameter, Parameter),
+  SetRelativeBase(Parameter),
+  Halt
+}
+
+impl Op {
+  fn size(self) -> usize {
+    match self {
+      Op::Add(..) | Op::Multiply(..) | Op::LessThan(..) | Op::Equals(..) => 4,
+      Op::JumpIfTrue(..) | Op::JumpIfFalse(..) => 3,
+      Op::Input(..) | Op::Output(..) | Op::SetRelativeBase(..) => 2,
+      Op::Halt => 1
+    }
+  }
+}
+
+fn decode<F>(read: F, pc: usize) -> Result<Op, VmError>
+where F: Fn(usize) -> i64 {
+  let code = read(pc);
+  let operation = OperationType::from_code(code % 100)
+    .ok_or(VmError::InvalidOpcode { pc, code })?;
+
+  let parameter = |i: usize| {
+    let value = read(pc + i + 1);
+
+    match code / 10i64.pow(2 + i as u32) % 10 {
+      0 => Ok(Parameter::Position(value)),
+      1 => Ok(Parameter::Immediate(value)),
+      2 => Ok(Parameter::Relative(value)),
+      _ => Err(VmError::InvalidParameterMode { pc, code })
+    }
+  };
+
+  Ok(match operation {
+    OperationType::Add => Op::Add(parameter(0)?, parameter(1)?, parameter(2)?),
+    OperationType::Multiply => Op::Multiply(parameter(0)?, parameter(1)?, parameter(2)?),
+    OperationType::Input => Op::Input(parameter(0)?),
+    OperationType::Output => Op::Output(parameter(0)?),
+    OperationType::JumpIfTrue => Op::JumpIfTrue(parameter(0)?, parameter(1)?),
+    OperationType::JumpIfFalse => Op::JumpIfFalse(parameter(0)?, parameter(1)?),
+    OperationType::LessThan => Op::LessThan(parameter(0)?, parameter(1)?, parameter(2)?),
+    OperationType::Equals => Op::Equals(parameter(0)?, parameter(1)?, parameter(2)?),
+    OperationType::SetRelativeBase => Op::SetRelativeBase(parameter(0)?),
+    OperationType::Halt => Op::Halt
+  })
+}
+
+/// Drop-in alternative to `Machine` that decodes every instruction once and
+/// caches it by address, so the hot loop neither re-parses instructions nor
+/// allocates. Writes into code invalidate the affected cache entries, so
+/// self-modifying programs behave exactly like on `Machine`.
+#[derive(Debug, Clone)]
+pub struct FastMachine<M: Memory = PagedMemory> {
+  pub memory: M,
+  pub instruction_pointer: usize,
+  pub relative_base: i64,
+  pub memory_limit: usize,
+  decoded: Vec<Option<Op>>
+}
+
+impl FastMachine {
+  pub fn new(program: Vec<i64>) -> FastMachine {
+    FastMachine::with_memory(PagedMemory::from(program))
+  }
+}
+
+impl<M: Memory> FastMachine<M> {
+  /// Only addresses inside the initial memory are cached, which keeps the
+  /// cache bounded by the program size.
+  pub fn with_memory(memory: M) -> FastMachine<M> {
+    FastMachine {
+      decoded: vec![None; memory.len()],
+      memory,
+      instruction_pointer: 0,
+      relative_base: 0,
+      memory_limit: M::DEFAULT_LIMIT
+    }
+  }
+
+  pub fn into_machine(self) -> Machine<M> {
+    Machine {
+      memory: self.memory,
+      instruction_pointer: self.instruction_pointer,
+      relative_base: self.relative_base,
+      memory_limit: self.memory_limit
+    }
+  }
+
+  fn fetch(&mut self) -> Result<Op, VmError> {
+    let pc = self.instruction_pointer;
+
+    if let Some(Some(op)) = self.decoded.get(pc) {
+      return Ok(*op);
+    }
+
+    let memory = &self.memory;
+    let op = decode(|address| memory.read(address), pc)?;
+
+    if let Some(entry) = self.decoded.get_mut(pc) {
+      *entry = Some(op);
+    }
+
+    Ok(op)
+  }
+
+  fn to_address(&self, address: i64) -> Result<usize, VmError> {
+    if address < 0 {
+      return Err(VmError::NegativeAddress {
+        pc: self.instruction_pointer,
+        address
+      });
+    }
+
+    Ok(address as usize)
+  }
+
+  fn checked(&self, value: Option<i64>) -> Result<i64, VmError> {
+    value.ok_or(VmError::Overflow { pc: self.instruction_pointer })
+  }
+
+  fn relative_address(&self, offset: i64) -> Result<i64, VmError> {
+    self.checked(self.relative_base.checked_add(offset))
+  }
+
+  fn touch_address(&mut self, address: i64) -> Result<usize, VmError> {
+    let pc = self.instruction_pointer;
+    let address = self.to_address(address)?;
+
+    if address >= self.memory_limit {
+      return Err(VmError::MemoryLimitExceeded { pc, address });
+    }
+
+    self.memory.resize(address + 1);
+    Ok(address)
+  }
+
+  fn read(&mut self, parameter: Parameter) -> Result<i64, VmError> {
+    let address = match parameter {
+      Parameter::Immediate(value) => return Ok(value),
+      Parameter::Position(address) => self.touch_address(address)?,
+      Parameter::Relative(offset) => {
+        let address = self.relative_address(offset)?;
+        self.touch_address(address)?
+      }
+    };
+
+    Ok(self.memory.read(address))
+  }
+
+  fn write(&mut self, parameter: Parameter, value: i64) -> Result<(), VmError> {
+    let address = match parameter {
+      Parameter::Immediate(_) => return Err(VmError::WriteToImmediate { pc: self.instruction_pointer }),
+      Parameter::Position(address) => self.touch_address(address)?,
+      Parameter::Relative(offset) => {
+        let address = self.relative_address(offset)?;
+        self.touch_address(address)?
+      }
+    };
+
+    self.memory.write(address, value);
+
+    let start = (address + 1).saturating_sub(MAX_INSTRUCTION_SIZE);
+    let end = (address + 1).min(self.decoded.len());
+
+    for entry in self.decoded.iter_mut().take(end).skip(start) {
+      *entry = None;
+    }
+
+    Ok(())
+  }
+
+  fn jump(&mut self, target: Parameter, condition: bool, size: usize) -> Result<(), VmError> {
+    if condition {
+      // Like on `Machine`, jumping out of memory halts instead of growing it
+      let target = self.read(target)?;
+      self.instruction_pointer = self.to_address(target)?;
+    } else {
+      self.instruction_pointer += size;
+    }
+
+    Ok(())
+  }
+
+  /// Same contract as `Machine::step`.
+  pub fn step(&mut self, input: &mut Option<i64>) -> Result<Option<ProgramResult>, VmError> {
+    if self.instruction_pointer >= self.memory.len() {
+      return Ok(Some(ProgramResult::Halt));
+    }
+
+    let op = self.fetch()?;
+    let mut result = None;
+
+    match op {
+      Op::Add(a, b, target) => {
+        let (a, b) = (self.read(a)?, self.read(b)?);
+        let value = self.checked(a.checked_add(b))?;
+        self.write(target, value)?;
+      },
+      Op::Multiply(a, b, target) => {
+        let (a, b) = (self.read(a)?, self.read(b)?);
+        let value = self.checked(a.checked_mul(b))?;
+        self.write(target, value)?;
+      },
+      Op::Input(target) => match input.take() {
+        Some(x) => self.write(target, x)?,
+        None => return Ok(Some(ProgramResult::WaitForInput))
+      },
+      Op::Output(a) => result = Some(ProgramResult::Output(self.read(a)?)),
+      Op::JumpIfTrue(a, target) => {
+        let condition = self.read(a)? != 0;
+        return self.jump(target, condition, op.size()).map(|_| None);
+      },
+      Op::JumpIfFalse(a, target) => {
+        let condition = self.read(a)? == 0;
+        return self.jump(target, condition, op.size()).map(|_| None);
+      },
+      Op::LessThan(a, b, target) => {
+        let value = self.read(a)? < self.read(b)?;
+        self.write(target, value as i64)?;
+      },
+      Op::Equals(a, b, target) => {
+        let value = self.read(a)? == self.read(b)?;
+        self.write(target, value as i64)?;
+      },
+      Op::SetRelativeBase(a) => {
+        let offset = self.read(a)?;
+        self.relative_base = self.relative_address(offset)?;
+      },
+      Op::Halt => return Ok(Some(ProgramResult::Halt))
+    }
+
+    self.instruction_pointer += op.size();
+    Ok(result)
+  }
+
+  pub fn run_until_io(&mut self, input: Option<i64>) -> Result<ProgramResult, VmError> {
+    let mut input = input;
+
+    loop {
+      if let Some(result) = self.step(&mut input)? {
+        return Ok(result);
+      }
+    }
+  }
+
+  pub fn run_with_inputs<I>(&mut self, inputs: I) -> Result<(Vec<i64>, ProgramResult), VmError>
+  where I: Iterator<Item = i64> {
+    run_with_inputs(self, inputs, FastMachine::step)
+  }
+
+  pub fn run_with_io<I: Input, O: Output>(&mut self, input: I, output: O) -> Result<ProgramResult, VmError> {
+    run_with_io(self, input, output, FastMachine::step)
+  }
+}
+
+impl<M: Memory> From<Machine<M>> for FastMachine<M> {
+  fn from(machine: Machine<M>) -> FastMachine<M> {
+    let mut result = FastMachine::with_memory(machine.memory);
+
+    result.instruction_pointer = machine.instruction_pointer;
+    result.relative_base = machine.relative_base;
+    result.memory_limit = machine.memory_limit;
+    result
+  }
+}
+
+#[test]
+fn self_modifying_code() {
+  use crate::{assemble, run_program_with_inputs};
+
+  let program = assemble("
+          in -> [n]
+    loop: add [n], #0 -> [out+1]
+    out:  out #0
+          add [n], #-1 -> [n]
+          jt [n], #loop
+          hlt
+    n:    data 0
+  ").unwrap();
+
+  let expected = run_program_with_inputs(&mut Machine::new(program.clone()), vec![3].into_iter());
+  let mut machine = FastMachine::new(program);
+
+  assert_eq!(expected, Ok((vec![3, 2, 1], ProgramResult::Halt)));
+  assert_eq!(machine.run_with_inputs(vec![3].into_iter()), expected);
+}
+
+#[test]
+fn out_of_range_jumps() {
+  let programs = vec![
+    vec![1105, 1, 100],
+    vec![1105, 1, 1 << 40],
+    vec![1105, 1, -1],
+    vec![1106, 0, 3, 99]
+  ];
+
+  for program in programs {
+    let expected = Machine::new(program.clone()).with_memory_limit(1 << 20).run_until_io(None);
+
+    if program[2] >= 0 {
+      assert_eq!(expected, Ok(ProgramResult::Halt));
+    }
+
+    let mut machine = FastMachine::new(program);
+
+    machine.memory_limit = 1 << 20;
+    assert_eq!(machine.run_until_io(None), expected);
+  }
+}
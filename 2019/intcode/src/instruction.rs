@@ -0,0 +1,162 @@
+use std::fmt;
+
+use crate::error::VmError;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParameterMode {
+  Position,
+  Immediate,
+  Relative
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OperationType {
+  Add,
+  Multiply,
+  Input,
+  Output,
+  JumpIfTrue,
+  JumpIfFalse,
+  LessThan,
+  Equals,
+  SetRelativeBase,
+  Halt
+}
+
+impl OperationType {
+  pub fn from_code(op_code: i64) -> Option<OperationType> {
+    Some(match op_code {
+      1 => OperationType::Add,
+      2 => OperationType::Multiply,
+      3 => OperationType::Input,
+      4 => OperationType::Output,
+      5 => OperationType::JumpIfTrue,
+      6 => OperationType::JumpIfFalse,
+      7 => OperationType::LessThan,
+      8 => OperationType::Equals,
+      9 => OperationType::SetRelativeBase,
+      99 => OperationType::Halt,
+      _ => return None
+    })
+  }
+
+  pub fn code(self) -> i64 {
+    match self {
+      OperationType::Add => 1,
+      OperationType::Multiply => 2,
+      OperationType::Input => 3,
+      OperationType::Output => 4,
+      OperationType::JumpIfTrue => 5,
+      OperationType::JumpIfFalse => 6,
+      OperationType::LessThan => 7,
+      OperationType::Equals => 8,
+      OperationType::SetRelativeBase => 9,
+      OperationType::Halt => 99
+    }
+  }
+
+  pub fn parameter_count(self) -> usize {
+    match self {
+      OperationType::Add | OperationType::Multiply => 3,
+      OperationType::LessThan | OperationType::Equals => 3,
+      OperationType::JumpIfTrue | OperationType::JumpIfFalse => 2,
+      OperationType::Input | OperationType::Output => 1,
+      OperationType::SetRelativeBase => 1,
+      OperationType::Halt => 0
+    }
+  }
+
+  /// Index of the parameter the operation writes to, if any.
+  pub fn output_parameter(self) -> Option<usize> {
+    match self {
+      OperationType::Add | OperationType::Multiply => Some(2),
+      OperationType::LessThan | OperationType::Equals => Some(2),
+      OperationType::Input => Some(0),
+      _ => None
+    }
+  }
+
+  pub fn mnemonic(self) -> &'static str {
+    match self {
+      OperationType::Add => "ADD",
+      OperationType::Multiply => "MUL",
+      OperationType::Input => "IN",
+      OperationType::Output => "OUT",
+      OperationType::JumpIfTrue => "JT",
+      OperationType::JumpIfFalse => "JF",
+      OperationType::LessThan => "LT",
+      OperationType::Equals => "EQ",
+      OperationType::SetRelativeBase => "ARB",
+      OperationType::Halt => "HLT"
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+  pub operation: OperationType,
+  pub inputs: Vec<(ParameterMode, i64)>
+}
+
+pub fn parse_instruction(program: &[i64], pc: usize) -> Result<Instruction, VmError> {
+  parse_instruction_with(|address| program.get(address).cloned().unwrap_or(0), pc)
+}
+
+/// Like `parse_instruction`, but reads memory through `read`, so it works
+/// for any memory layout.
+pub fn parse_instruction_with<F>(read: F, pc: usize) -> Result<Instruction, VmError>
+where F: Fn(usize) -> i64 {
+  let instruction_code = read(pc);
+  let op_code = instruction_code % 100;
+
+  let operation = OperationType::from_code(op_code)
+    .ok_or(VmError::InvalidOpcode { pc, code: instruction_code })?;
+
+  let get_parameter_mode = |i| {
+    match instruction_code / 10i64.pow(2 + i as u32) % 10 {
+      0 => Ok(ParameterMode::Position),
+      1 => Ok(ParameterMode::Immediate),
+      2 => Ok(ParameterMode::Relative),
+      _ => Err(VmError::InvalidParameterMode { pc, code: instruction_code })
+    }
+  };
+
+  Ok(Instruction {
+    operation,
+    inputs: (0..operation.parameter_count())
+      .map(|i| Ok((get_parameter_mode(i)?, read(pc + i + 1))))
+      .collect::<Result<_, _>>()?
+  })
+}
+
+pub fn format_parameter((mode, value): (ParameterMode, i64)) -> String {
+  match mode {
+    ParameterMode::Position => format!("[{}]", value),
+    ParameterMode::Immediate => format!("#{}", value),
+    ParameterMode::Relative if value < 0 => format!("[rb{}]", value),
+    ParameterMode::Relative => format!("[rb+{}]", value)
+  }
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let output_parameter = self.operation.output_parameter();
+    let inputs = self.inputs.iter()
+      .enumerate()
+      .filter(|&(i, _)| Some(i) != output_parameter)
+      .map(|(_, &parameter)| format_parameter(parameter))
+      .collect::<Vec<_>>();
+
+    write!(f, "{}", self.operation.mnemonic())?;
+
+    if !inputs.is_empty() {
+      write!(f, " {}", inputs.join(", "))?;
+    }
+
+    if let Some(i) = output_parameter {
+      write!(f, " -> {}", format_parameter(self.inputs[i]))?;
+    }
+
+    Ok(())
+  }
+}
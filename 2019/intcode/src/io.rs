@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+use crate::error::VmError;
+use crate::machine::ProgramResult;
+
+/// Source of input values for a machine. Returning `None` means no input is
+/// available right now, which makes the machine stop with
+/// `ProgramResult::WaitForInput`.
+pub trait Input {
+  fn next_input(&mut self) -> Option<i64>;
+}
+
+/// Sink for the values a machine outputs.
+pub trait Output {
+  fn write_output(&mut self, value: i64);
+}
+
+impl<T: Input + ?Sized> Input for &mut T {
+  fn next_input(&mut self) -> Option<i64> {
+    (**self).next_input()
+  }
+}
+
+impl<T: Output + ?Sized> Output for &mut T {
+  fn write_output(&mut self, value: i64) {
+    (**self).write_output(value)
+  }
+}
+
+/// Feeds the values of an iterator as input.
+pub struct IteratorInput<I>(pub I);
+
+impl<I: Iterator<Item = i64>> Input for IteratorInput<I> {
+  fn next_input(&mut self) -> Option<i64> {
+    self.0.next()
+  }
+}
+
+impl Input for VecDeque<i64> {
+  fn next_input(&mut self) -> Option<i64> {
+    self.pop_front()
+  }
+}
+
+impl Output for VecDeque<i64> {
+  fn write_output(&mut self, value: i64) {
+    self.push_back(value);
+  }
+}
+
+impl Output for Vec<i64> {
+  fn write_output(&mut self, value: i64) {
+    self.push(value);
+  }
+}
+
+/// Blocks until a value arrives. Once all senders are gone, the machine
+/// stops waiting for input.
+impl Input for Receiver<i64> {
+  fn next_input(&mut self) -> Option<i64> {
+    self.recv().ok()
+  }
+}
+
+/// Values sent after the receiver is gone are dropped.
+impl Output for Sender<i64> {
+  fn write_output(&mut self, value: i64) {
+    self.send(value).ok();
+  }
+}
+
+/// Feeds text read line by line from `reader` as ASCII codes, so an ASCII
+/// program can be driven interactively from stdin.
+pub struct AsciiInput<R> {
+  reader: R,
+  buffer: VecDeque<i64>
+}
+
+impl<R: BufRead> AsciiInput<R> {
+  pub fn new(reader: R) -> AsciiInput<R> {
+    AsciiInput { reader, buffer: VecDeque::new() }
+  }
+}
+
+impl<R: BufRead> Input for AsciiInput<R> {
+  fn next_input(&mut self) -> Option<i64> {
+    if self.buffer.is_empty() {
+      let mut line = String::new();
+
+      match self.reader.read_line(&mut line) {
+        Ok(n) if n > 0 => {
+          let line = line.trim_end_matches(&['\r', '\n'][..]);
+          self.buffer.extend(line.chars().chain(Some('\n')).map(|c| c as i64));
+        },
+        _ => return None
+      }
+    }
+
+    self.buffer.pop_front()
+  }
+}
+
+/// Writes ASCII output as text to `writer`. Values outside of the ASCII
+/// range, like puzzle answers, are collected in `values` instead.
+pub struct AsciiOutput<W> {
+  writer: W,
+  pub values: Vec<i64>
+}
+
+impl<W: Write> AsciiOutput<W> {
+  pub fn new(writer: W) -> AsciiOutput<W> {
+    AsciiOutput { writer, values: vec![] }
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+}
+
+impl<W: Write> Output for AsciiOutput<W> {
+  fn write_output(&mut self, value: i64) {
+    if (0..128).contains(&value) {
+      self.writer.write_all(&[value as u8]).ok();
+
+      if value == '\n' as i64 {
+        self.writer.flush().ok();
+      }
+    } else {
+      self.values.push(value);
+    }
+  }
+}
+
+/// Drives `step` until the machine halts or `input` runs dry, taking input
+/// only when the machine actually asks for it.
+pub(crate) fn run_with_io<S, I, O, F>(state: &mut S, mut input: I, mut output: O, mut step: F) -> Result<ProgramResult, VmError>
+where I: Input, O: Output, F: FnMut(&mut S, &mut Option<i64>) -> Result<Option<ProgramResult>, VmError> {
+  let mut value = None;
+
+  loop {
+    match step(state, &mut value)? {
+      None => {},
+      Some(ProgramResult::Output(x)) => output.write_output(x),
+      Some(ProgramResult::WaitForInput) => match input.next_input() {
+        Some(x) => value = Some(x),
+        None => return Ok(ProgramResult::WaitForInput)
+      },
+      Some(ProgramResult::Halt) => return Ok(ProgramResult::Halt)
+    }
+  }
+}
+
+pub(crate) fn run_with_inputs<S, I, F>(state: &mut S, inputs: I, step: F) -> Result<(Vec<i64>, ProgramResult), VmError>
+where I: Iterator<Item = i64>, F: FnMut(&mut S, &mut Option<i64>) -> Result<Option<ProgramResult>, VmError> {
+  let mut outputs = vec![];
+  let result = run_with_io(state, IteratorInput(inputs), &mut outputs, step)?;
+
+  Ok((outputs, result))
+}
+
+#[test]
+fn wire_machines() {
+  use std::sync::mpsc::channel;
+  use std::thread;
+  use crate::{assemble, Machine};
+
+  let program = assemble("
+    loop: in -> [x]
+          jf [x], #end
+          mul [x], #2 -> [x]
+          out [x]
+          jt #1, #loop
+    end:  hlt
+    x:    data 0
+  ").unwrap();
+
+  let (sender, mut receiver) = channel();
+  let mut first = Machine::new(program.clone());
+  let mut second = Machine::new(program);
+
+  let handle = thread::spawn(move || {
+    first.run_with_io(IteratorInput(vec![1, 2, 3, 0].into_iter()), sender)
+  });
+
+  let mut outputs = vec![];
+
+  assert_eq!(second.run_with_io(&mut receiver, &mut outputs), Ok(ProgramResult::WaitForInput));
+  assert_eq!(handle.join().unwrap(), Ok(ProgramResult::Halt));
+  assert_eq!(outputs, vec![4, 8, 12]);
+}
+
+#[test]
+fn ascii_streams() {
+  use crate::{assemble, Machine};
+
+  let program = assemble("
+          out #1000
+    loop: in -> [c]
+          out [c]
+          jt #1, #loop
+    c:    data 0
+  ").unwrap();
+
+  let mut input = AsciiInput::new(&b"hello\r\nworld"[..]);
+  let mut output = AsciiOutput::new(vec![]);
+
+  assert_eq!(Machine::new(program).run_with_io(&mut input, &mut output), Ok(ProgramResult::WaitForInput));
+  assert_eq!(output.values, vec![1000]);
+  assert_eq!(String::from_utf8(output.into_inner()).unwrap(), "hello\nworld\n");
+}
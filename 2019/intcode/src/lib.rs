@@ -0,0 +1,140 @@
+mod asm;
+mod bus;
+mod debugger;
+mod disasm;
+mod error;
+mod fast;
+mod instruction;
+mod io;
+mod machine;
+mod memory;
+mod network;
+mod snapshot;
+mod terminal;
+mod trace;
+
+pub use asm::{assemble, AsmError};
+pub use debugger::{Debugger, HistoryEntry, StopReason, DEFAULT_HISTORY_SIZE};
+pub use disasm::{disassemble, Listing, ListingEntry};
+pub use error::VmError;
+pub use fast::FastMachine;
+pub use instruction::{format_parameter, parse_instruction, parse_instruction_with, Instruction, OperationType, ParameterMode};
+pub use io::{AsciiInput, AsciiOutput, Input, IteratorInput, Output};
+pub use machine::{Machine, ProgramResult, DEFAULT_MEMORY_LIMIT};
+pub use memory::{Memory, PagedMemory, DENSE_MEMORY_LIMIT, PAGE_SIZE};
+pub use network::{Network, NetworkEvent, Packet, Topology};
+pub use snapshot::Snapshot;
+pub use terminal::{replay, Terminal, TerminalError, Transcript, TranscriptEntry};
+pub use trace::{Profile, TraceEntry, Tracer};
+
+use io::run_with_inputs;
+
+pub fn parse_program(input: &str) -> Vec<i64> {
+  input.split(',')
+    .filter_map(|x| x.trim().parse::<i64>().ok())
+    .collect()
+}
+
+pub fn run_program<M: Memory>(state: &mut Machine<M>, input: Option<i64>) -> Result<ProgramResult, VmError> {
+  state.run_until_io(input)
+}
+
+pub fn run_program_with_inputs<M, I>(state: &mut Machine<M>, inputs: I) -> Result<(Vec<i64>, ProgramResult), VmError>
+where M: Memory, I: Iterator<Item = i64> {
+  run_with_inputs(state, inputs, Machine::step)
+}
+
+pub fn run_ascii_program_with_input<M: Memory>(state: &mut Machine<M>, input: &str) -> Result<(String, ProgramResult), VmError> {
+  let inputs = input.chars().map(|c| c as i64);
+  let (outputs, result) = run_program_with_inputs(state, inputs)?;
+  let output = outputs.into_iter()
+    .map(|x| x as u8 as char)
+    .collect::<String>();
+
+  Ok((output, result))
+}
+
+#[test]
+fn quine() {
+  let program = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+  let (outputs, result) = run_program_with_inputs(&mut Machine::new(program.clone()), std::iter::empty()).unwrap();
+
+  assert_eq!(outputs, program);
+  assert_eq!(result, ProgramResult::Halt);
+}
+
+#[test]
+fn compare_with_eight() {
+  let program = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+
+  for &(input, expected) in &[(7, 1), (8, 0), (9, 0)] {
+    let (outputs, _) = run_program_with_inputs(&mut Machine::new(program.clone()), std::iter::once(input)).unwrap();
+    assert_eq!(outputs, vec![expected]);
+  }
+}
+
+#[test]
+fn wait_for_input() {
+  let mut machine = Machine::new(vec![3, 0, 4, 0, 99]);
+
+  assert_eq!(run_program(&mut machine, None), Ok(ProgramResult::WaitForInput));
+  assert_eq!(machine.instruction_pointer, 0);
+  assert_eq!(run_program(&mut machine, Some(42)), Ok(ProgramResult::Output(42)));
+  assert_eq!(run_program(&mut machine, None), Ok(ProgramResult::Halt));
+}
+
+#[test]
+fn corrupt_programs() {
+  let cases = vec![
+    (vec![1, 0, 0, 0, 42], VmError::InvalidOpcode { pc: 4, code: 42 }),
+    (vec![301, 0, 0, 0], VmError::InvalidParameterMode { pc: 0, code: 301 }),
+    (vec![1, -1, 0, 0], VmError::NegativeAddress { pc: 0, address: -1 }),
+    (vec![109, -5, 204, 0], VmError::NegativeAddress { pc: 2, address: -5 }),
+    (vec![11101, 1, 1, 0], VmError::WriteToImmediate { pc: 0 }),
+    (vec![1, 0, 0, 1 << 40], VmError::MemoryLimitExceeded { pc: 0, address: 1 << 40 })
+  ];
+
+  for (program, error) in cases {
+    assert_eq!(run_program(&mut Machine::new(program), None), Err(error));
+  }
+}
+
+#[test]
+fn overflowing_programs() {
+  let cases = vec![
+    (vec![1101, i64::MAX, 1, 0], 0),
+    (vec![1102, i64::MIN, -1, 0], 0),
+    (vec![109, i64::MAX, 109, 1], 2),
+    (vec![109, i64::MAX, 204, 1], 2)
+  ];
+
+  for (program, pc) in cases {
+    let expected = Err(VmError::Overflow { pc });
+
+    assert_eq!(run_program(&mut Machine::new(program.clone()), None), expected);
+    assert_eq!(FastMachine::new(program).run_until_io(None), expected);
+  }
+}
+
+#[test]
+fn large_addresses() {
+  let program = vec![1101, 7, 0, 1_000_000_000, 4, 1_000_000_000, 99];
+  let mut machine = Machine::new(program.clone());
+
+  assert_eq!(run_program(&mut machine, None), Ok(ProgramResult::Output(7)));
+  assert_eq!(machine.memory.allocated_pages(), 2);
+  assert_eq!(machine.memory.to_vec(), None);
+
+  let expected = Err(VmError::MemoryLimitExceeded { pc: 0, address: 1_000_000_000 });
+
+  let mut machine = Machine::new(program.clone()).with_memory_limit(1 << 20);
+  assert_eq!(run_program(&mut machine, None), expected);
+
+  let mut machine = Machine::with_memory(program.clone()).with_memory_limit(1 << 20);
+  assert_eq!(run_program(&mut machine, None), expected);
+
+  // Dense memory would have to allocate every address below the write.
+  let mut machine = Machine::with_memory(program.clone());
+  assert_eq!(run_program(&mut machine, None), expected);
+  assert_eq!(FastMachine::with_memory(program).run_until_io(None), expected);
+}
@@ -0,0 +1,223 @@
+use crate::error::VmError;
+use crate::instruction::{parse_instruction_with, Instruction, OperationType, ParameterMode};
+use crate::io::{run_with_io, Input, Output};
+use crate::memory::{Memory, PagedMemory};
+use crate::snapshot::Snapshot;
+
+/// Default size of the address space. Paged memory is sparse, so this only
+/// bounds the addresses a program may touch, not what gets allocated. Dense
+/// memory defaults to the much smaller `DENSE_MEMORY_LIMIT`.
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 32;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ProgramResult {
+  Output(i64),
+  WaitForInput,
+  Halt
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<M: Memory = PagedMemory> {
+  pub memory: M,
+  pub instruction_pointer: usize,
+  pub relative_base: i64,
+  pub memory_limit: usize
+}
+
+impl Machine {
+  pub fn new(program: Vec<i64>) -> Machine {
+    Machine::with_memory(PagedMemory::from(program))
+  }
+
+  pub fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      memory: self.memory.clone(),
+      instruction_pointer: self.instruction_pointer,
+      relative_base: self.relative_base,
+      memory_limit: self.memory_limit
+    }
+  }
+
+  pub fn restore(&mut self, snapshot: &Snapshot) {
+    self.memory = snapshot.memory.clone();
+    self.instruction_pointer = snapshot.instruction_pointer;
+    self.relative_base = snapshot.relative_base;
+    self.memory_limit = snapshot.memory_limit;
+  }
+}
+
+impl<M: Memory> Machine<M> {
+  pub fn with_memory(memory: M) -> Machine<M> {
+    Machine {
+      memory,
+      instruction_pointer: 0,
+      relative_base: 0,
+      memory_limit: M::DEFAULT_LIMIT
+    }
+  }
+
+  /// Programs that touch an address at or above `limit` fail with
+  /// `VmError::MemoryLimitExceeded`.
+  pub fn with_memory_limit(mut self, limit: usize) -> Machine<M> {
+    self.memory_limit = limit;
+    self
+  }
+
+  fn extend_memory(&mut self, index: usize) -> Result<(), VmError> {
+    if index >= self.memory_limit {
+      return Err(VmError::MemoryLimitExceeded {
+        pc: self.instruction_pointer,
+        address: index
+      });
+    }
+
+    self.memory.resize(index + 1);
+
+    Ok(())
+  }
+
+  pub fn instruction_at(&self, address: usize) -> Result<Instruction, VmError> {
+    parse_instruction_with(|address| self.memory.read(address), address)
+  }
+
+  /// Returns an independent copy of the machine. With `PagedMemory`, pages
+  /// are shared until either machine writes to them, so this is cheap enough
+  /// to do at every decision point of a search.
+  pub fn fork(&self) -> Machine<M> {
+    self.clone()
+  }
+
+  fn to_address(&self, address: i64) -> Result<usize, VmError> {
+    if address < 0 {
+      return Err(VmError::NegativeAddress {
+        pc: self.instruction_pointer,
+        address
+      });
+    }
+
+    Ok(address as usize)
+  }
+
+  fn checked(&self, value: Option<i64>) -> Result<i64, VmError> {
+    value.ok_or(VmError::Overflow { pc: self.instruction_pointer })
+  }
+
+  fn get_input_index(&mut self, instruction: &Instruction, index: usize) -> Result<usize, VmError> {
+    let j = match instruction.inputs[index] {
+      (ParameterMode::Relative, d) => {
+        let address = self.checked(self.relative_base.checked_add(d))?;
+        self.to_address(address)?
+      },
+      (ParameterMode::Position, j) => self.to_address(j)?,
+      (ParameterMode::Immediate, _) => {
+        return Err(VmError::WriteToImmediate { pc: self.instruction_pointer });
+      }
+    };
+
+    self.extend_memory(j)?;
+    Ok(j)
+  }
+
+  fn get_input(&mut self, instruction: &Instruction, index: usize) -> Result<i64, VmError> {
+    match instruction.inputs[index] {
+      (ParameterMode::Immediate, value) => Ok(value),
+      _ => {
+        let j = self.get_input_index(instruction, index)?;
+        Ok(self.memory.read(j))
+      }
+    }
+  }
+
+  fn jump(&mut self, instruction: &Instruction, condition: bool) -> Result<(), VmError> {
+    if condition {
+      let target = self.get_input(instruction, 1)?;
+      self.instruction_pointer = self.to_address(target)?;
+    } else {
+      self.instruction_pointer += instruction.inputs.len() + 1;
+    }
+
+    Ok(())
+  }
+
+  /// Executes a single instruction. Returns `None` if the machine can keep
+  /// running, otherwise the event that interrupted it. `input` is taken by
+  /// the first input instruction encountered.
+  pub fn step(&mut self, input: &mut Option<i64>) -> Result<Option<ProgramResult>, VmError> {
+    if self.instruction_pointer >= self.memory.len() {
+      return Ok(Some(ProgramResult::Halt));
+    }
+
+    let instruction = self.instruction_at(self.instruction_pointer)?;
+
+    let (target_value, output_index) = match instruction.operation {
+      OperationType::Add => {
+        let (a, b) = (self.get_input(&instruction, 0)?, self.get_input(&instruction, 1)?);
+        let value = self.checked(a.checked_add(b))?;
+        (value, Some(2))
+      },
+      OperationType::Multiply => {
+        let (a, b) = (self.get_input(&instruction, 0)?, self.get_input(&instruction, 1)?);
+        let value = self.checked(a.checked_mul(b))?;
+        (value, Some(2))
+      },
+      OperationType::Input => match input.take() {
+        Some(x) => (x, Some(0)),
+        None => return Ok(Some(ProgramResult::WaitForInput))
+      },
+      OperationType::Output => (self.get_input(&instruction, 0)?, None),
+      OperationType::LessThan => {
+        let value = self.get_input(&instruction, 0)? < self.get_input(&instruction, 1)?;
+        (value as i64, Some(2))
+      },
+      OperationType::Equals => {
+        let value = self.get_input(&instruction, 0)? == self.get_input(&instruction, 1)?;
+        (value as i64, Some(2))
+      },
+      OperationType::JumpIfTrue => {
+        let condition = self.get_input(&instruction, 0)? != 0;
+        self.jump(&instruction, condition)?;
+        return Ok(None);
+      },
+      OperationType::JumpIfFalse => {
+        let condition = self.get_input(&instruction, 0)? == 0;
+        self.jump(&instruction, condition)?;
+        return Ok(None);
+      },
+      OperationType::SetRelativeBase => {
+        let offset = self.get_input(&instruction, 0)?;
+        self.relative_base = self.checked(self.relative_base.checked_add(offset))?;
+        self.instruction_pointer += instruction.inputs.len() + 1;
+        return Ok(None);
+      },
+      OperationType::Halt => return Ok(Some(ProgramResult::Halt))
+    };
+
+    let result = match output_index {
+      Some(i) => {
+        let target_index = self.get_input_index(&instruction, i)?;
+        self.memory.write(target_index, target_value);
+        None
+      },
+      None => Some(ProgramResult::Output(target_value))
+    };
+
+    self.instruction_pointer += instruction.inputs.len() + 1;
+    Ok(result)
+  }
+
+  pub fn run_until_io(&mut self, input: Option<i64>) -> Result<ProgramResult, VmError> {
+    let mut input = input;
+
+    loop {
+      if let Some(result) = self.step(&mut input)? {
+        return Ok(result);
+      }
+    }
+  }
+
+  /// Runs until the machine halts or `input` has nothing more to give,
+  /// forwarding every output to `output`.
+  pub fn run_with_io<I: Input, O: Output>(&mut self, input: I, output: O) -> Result<ProgramResult, VmError> {
+    run_with_io(self, input, output, Machine::step)
+  }
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+use crate::machine::DEFAULT_MEMORY_LIMIT;
+
+pub const PAGE_SIZE: usize = 1024;
+
+/// Dense memory allocates every address below the highest one touched, so
+/// its address space is kept small enough to fit: 128 MB worth of words.
+pub const DENSE_MEMORY_LIMIT: usize = 1 << 24;
+
+type Page = [i64; PAGE_SIZE];
+
+static ZERO: i64 = 0;
+
+/// Backing store of an Intcode machine. Memory is conceptually infinite and
+/// zero initialized; `len` is one past the highest address touched so far.
+pub trait Memory: Clone {
+  /// Memory limit of machines created with this backend.
+  const DEFAULT_LIMIT: usize = DEFAULT_MEMORY_LIMIT;
+
+  fn len(&self) -> usize;
+
+  /// Reads the value at `address`, treating untouched memory as zero.
+  fn read(&self, address: usize) -> i64;
+
+  fn write(&mut self, address: usize, value: i64);
+
+  /// Grows the memory so that it is at least `len` long.
+  fn resize(&mut self, len: usize);
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Copies the memory into dense memory, or `None` if it is longer than
+  /// `DENSE_MEMORY_LIMIT`.
+  fn to_vec(&self) -> Option<Vec<i64>> {
+    if self.len() > DENSE_MEMORY_LIMIT {
+      return None;
+    }
+
+    Some((0..self.len()).map(|address| self.read(address)).collect())
+  }
+}
+
+impl Memory for Vec<i64> {
+  const DEFAULT_LIMIT: usize = DENSE_MEMORY_LIMIT;
+
+  fn len(&self) -> usize {
+    Vec::len(self)
+  }
+
+  fn read(&self, address: usize) -> i64 {
+    self.get(address).cloned().unwrap_or(0)
+  }
+
+  fn write(&mut self, address: usize, value: i64) {
+    Memory::resize(self, address + 1);
+    self[address] = value;
+  }
+
+  fn resize(&mut self, len: usize) {
+    if len > Vec::len(self) {
+      Vec::resize(self, len, 0);
+    }
+  }
+
+  fn to_vec(&self) -> Option<Vec<i64>> {
+    Some(self.clone())
+  }
+}
+
+/// Sparse Intcode memory split into fixed size pages. Pages are only
+/// allocated once written to, and they are shared between clones until one
+/// of them writes to it, so cloning a machine costs one reference count per
+/// page instead of a copy of the whole memory.
+#[derive(Debug, Clone, Default)]
+pub struct PagedMemory {
+  pages: BTreeMap<usize, Arc<Page>>,
+  len: usize
+}
+
+impl PagedMemory {
+  pub fn new() -> PagedMemory {
+    PagedMemory::default()
+  }
+
+  pub fn get(&self, address: usize) -> Option<i64> {
+    if address >= self.len {
+      return None;
+    }
+
+    Some(self.read(address))
+  }
+
+  fn page_mut(&mut self, address: usize) -> &mut Page {
+    let page = self.pages.entry(address / PAGE_SIZE)
+      .or_insert_with(|| Arc::new([0; PAGE_SIZE]));
+
+    Arc::make_mut(page)
+  }
+
+  /// Number of pages that are actually allocated.
+  pub fn allocated_pages(&self) -> usize {
+    self.pages.len()
+  }
+
+  /// Number of pages that are shared with another clone.
+  pub fn shared_pages(&self) -> usize {
+    self.pages.values()
+      .filter(|page| Arc::strong_count(page) > 1)
+      .count()
+  }
+
+  /// Allocated pages with their start address, in ascending order.
+  pub fn pages(&self) -> impl Iterator<Item = (usize, &[i64])> {
+    self.pages.iter()
+      .map(move |(&index, page)| {
+        let start = index * PAGE_SIZE;
+        (start, &page[..PAGE_SIZE.min(self.len - start)])
+      })
+  }
+}
+
+impl Memory for PagedMemory {
+  fn len(&self) -> usize {
+    self.len
+  }
+
+  fn read(&self, address: usize) -> i64 {
+    match self.pages.get(&(address / PAGE_SIZE)) {
+      Some(page) => page[address % PAGE_SIZE],
+      None => 0
+    }
+  }
+
+  fn write(&mut self, address: usize, value: i64) {
+    self.resize(address + 1);
+    self.page_mut(address)[address % PAGE_SIZE] = value;
+  }
+
+  fn resize(&mut self, len: usize) {
+    self.len = self.len.max(len);
+  }
+}
+
+impl From<Vec<i64>> for PagedMemory {
+  fn from(values: Vec<i64>) -> PagedMemory {
+    let pages = values.chunks(PAGE_SIZE)
+      .enumerate()
+      .map(|(index, chunk)| {
+        let mut page = [0; PAGE_SIZE];
+        page[..chunk.len()].copy_from_slice(chunk);
+        (index, Arc::new(page))
+      })
+      .collect::<BTreeMap<_, _>>();
+
+    PagedMemory { pages, len: values.len() }
+  }
+}
+
+impl Index<usize> for PagedMemory {
+  type Output = i64;
+
+  fn index(&self, address: usize) -> &i64 {
+    assert!(address < self.len, "address {} out of bounds", address);
+
+    match self.pages.get(&(address / PAGE_SIZE)) {
+      Some(page) => &page[address % PAGE_SIZE],
+      None => &ZERO
+    }
+  }
+}
+
+impl IndexMut<usize> for PagedMemory {
+  fn index_mut(&mut self, address: usize) -> &mut i64 {
+    assert!(address < self.len, "address {} out of bounds", address);
+    &mut self.page_mut(address)[address % PAGE_SIZE]
+  }
+}
+
+#[test]
+fn copy_on_write() {
+  let mut memory = PagedMemory::from((0..3000).collect::<Vec<i64>>());
+  let mut copy = memory.clone();
+
+  assert_eq!(memory.shared_pages(), 3);
+
+  copy[2000] = -1;
+  copy.resize(5000);
+
+  assert_eq!(memory[2000], 2000);
+  assert_eq!(copy[2000], -1);
+  assert_eq!(copy.read(4999), 0);
+  assert_eq!(memory.get(4999), None);
+  assert_eq!(memory.shared_pages(), 2);
+
+  memory[0] = 5;
+
+  assert_eq!(copy[0], 0);
+  assert_eq!(memory.to_vec().map(|values| values.len()), Some(3000));
+}
+
+#[test]
+fn sparse_pages() {
+  let mut memory = PagedMemory::from(vec![1, 2, 3]);
+
+  memory.write(1_000_000_000, 42);
+
+  assert_eq!(memory.len(), 1_000_000_001);
+  assert_eq!(memory.allocated_pages(), 2);
+  assert_eq!(memory.read(999_999_999), 0);
+  assert_eq!(memory[1_000_000_000], 42);
+
+  let pages = memory.pages().map(|(start, values)| (start, values.len())).collect::<Vec<_>>();
+  assert_eq!(pages, vec![(0, PAGE_SIZE), (1_000_000_000 / PAGE_SIZE * PAGE_SIZE, 1_000_000_001 % PAGE_SIZE)]);
+}
@@ -0,0 +1,470 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use crate::bus::run_bus_threaded;
+use crate::error::VmError;
+use crate::io::Output;
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::{Memory, PagedMemory};
+
+/// How the outputs of the machines in a `Network` are routed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Topology {
+  /// Every output is fed to the next machine, the outputs of the last
+  /// machine leave the network.
+  Pipeline,
+  /// Like a pipeline, but the last machine feeds the first one.
+  Ring,
+  /// Machines send packets as `address, x, y` triples and get one waiting
+  /// packet or `-1` as input each round. Packets sent to `nat` are held
+  /// back, and the last one is sent to machine 0 once the network is idle.
+  Bus { nat: Option<usize> }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Packet {
+  pub from: usize,
+  pub to: usize,
+  pub x: i64,
+  pub y: i64
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkEvent {
+  /// A value a machine in a pipeline or ring has output.
+  Output { from: usize, value: i64 },
+  /// A packet has been sent on the bus, including the ones the NAT sends.
+  Packet(Packet),
+  /// No machine has anything to do.
+  Idle,
+  Halt(usize),
+  Crash(usize, VmError)
+}
+
+/// Runs several machines round-robin, routing their outputs according to a
+/// `Topology`. Iterating over the network runs it deterministically and
+/// yields its events; iteration ends once every machine has stopped.
+pub struct Network<M: Memory = PagedMemory> {
+  pub machines: Vec<Machine<M>>,
+  pub topology: Topology,
+  inputs: Vec<VecDeque<i64>>,
+  packets: Vec<VecDeque<Packet>>,
+  outputs: Vec<Vec<i64>>,
+  stopped: Vec<bool>,
+  idle: Vec<bool>,
+  nat: Option<Packet>,
+  events: VecDeque<NetworkEvent>
+}
+
+impl<M: Memory> Network<M> {
+  pub fn new(machines: Vec<Machine<M>>, topology: Topology) -> Network<M> {
+    let count = machines.len();
+
+    Network {
+      machines,
+      topology,
+      inputs: vec![VecDeque::new(); count],
+      packets: vec![VecDeque::new(); count],
+      outputs: vec![vec![]; count],
+      stopped: vec![false; count],
+      idle: vec![false; count],
+      nat: None,
+      events: VecDeque::new()
+    }
+  }
+
+  /// Queues a raw input value for machine `id`, e.g. a phase setting or a
+  /// network address.
+  pub fn send(&mut self, id: usize, value: i64) {
+    self.inputs[id].push_back(value);
+  }
+
+  pub fn is_idle(&self) -> bool {
+    self.idle.iter().all(|&idle| idle)
+  }
+
+  /// The packet the NAT currently holds back.
+  pub fn nat(&self) -> Option<Packet> {
+    self.nat
+  }
+
+  fn route(&mut self, from: usize) {
+    let count = self.machines.len();
+
+    match self.topology {
+      Topology::Pipeline | Topology::Ring => {
+        for value in self.outputs[from].drain(..) {
+          self.events.push_back(NetworkEvent::Output { from, value });
+
+          match (self.topology, from + 1) {
+            (Topology::Ring, to) => self.inputs[to % count].push_back(value),
+            (_, to) if to < count => self.inputs[to].push_back(value),
+            _ => {}
+          }
+        }
+      },
+      Topology::Bus { nat } => {
+        let complete = self.outputs[from].len() / 3 * 3;
+        let values = self.outputs[from].drain(..complete).collect::<Vec<_>>();
+
+        for chunk in values.chunks(3) {
+          let packet = Packet { from, to: chunk[0] as usize, x: chunk[1], y: chunk[2] };
+
+          self.events.push_back(NetworkEvent::Packet(packet));
+
+          if nat == Some(packet.to) {
+            self.nat = Some(packet);
+          } else if let Some(queue) = self.packets.get_mut(packet.to) {
+            queue.push_back(packet);
+          }
+        }
+      }
+    }
+  }
+
+  fn run_round(&mut self) {
+    for id in 0..self.machines.len() {
+      if self.stopped[id] {
+        continue;
+      }
+
+      let has_input = match self.topology {
+        Topology::Bus { .. } => match self.packets[id].pop_front() {
+          Some(packet) => {
+            self.inputs[id].extend(&[packet.x, packet.y]);
+            true
+          },
+          None => {
+            self.inputs[id].push_back(-1);
+            false
+          }
+        },
+        _ => !self.inputs[id].is_empty()
+      };
+
+      let sent = self.outputs[id].len();
+      let result = self.machines[id].run_with_io(&mut self.inputs[id], &mut self.outputs[id]);
+      let has_output = self.outputs[id].len() > sent;
+
+      self.idle[id] = !has_input && !has_output;
+      self.route(id);
+
+      match result {
+        Ok(ProgramResult::Halt) => {
+          self.stopped[id] = true;
+          self.idle[id] = true;
+          self.events.push_back(NetworkEvent::Halt(id));
+        },
+        Err(err) => {
+          self.stopped[id] = true;
+          self.idle[id] = true;
+          self.events.push_back(NetworkEvent::Crash(id, err));
+        },
+        _ => {}
+      }
+    }
+
+    if self.is_idle() && self.stopped.iter().any(|&stopped| !stopped) {
+      self.events.push_back(NetworkEvent::Idle);
+
+      if let (Topology::Bus { nat: Some(address) }, Some(packet)) = (self.topology, self.nat.take()) {
+        let packet = Packet { from: address, to: 0, ..packet };
+
+        self.events.push_back(NetworkEvent::Packet(packet));
+        self.packets[0].push_back(packet);
+      }
+    }
+  }
+}
+
+impl<M: Memory> Iterator for Network<M> {
+  type Item = NetworkEvent;
+
+  fn next(&mut self) -> Option<NetworkEvent> {
+    loop {
+      if let Some(event) = self.events.pop_front() {
+        return Some(event);
+      }
+
+      if self.stopped.iter().all(|&stopped| stopped) {
+        return None;
+      }
+
+      self.run_round();
+    }
+  }
+}
+
+struct Link {
+  from: usize,
+  next: Option<Sender<i64>>,
+  events: Sender<NetworkEvent>
+}
+
+impl Output for Link {
+  fn write_output(&mut self, value: i64) {
+    self.events.send(NetworkEvent::Output { from: self.from, value }).ok();
+
+    if let Some(next) = &self.next {
+      next.send(value).ok();
+    }
+  }
+}
+
+impl<M: Memory + Send + 'static> Network<M> {
+  /// Runs every machine on its own thread, connected by channels, and
+  /// returns the stream of events. Events of a single machine arrive in
+  /// order, but the interleaving between machines is not deterministic.
+  pub fn run_threaded(self) -> Receiver<NetworkEvent> {
+    if let Topology::Bus { nat } = self.topology {
+      return run_bus_threaded(self.machines, self.inputs, nat);
+    }
+
+    let count = self.machines.len();
+    let (event_sender, event_receiver) = channel();
+    let (mut senders, receivers): (Vec<_>, Vec<_>) = (0..count).map(|_| channel()).unzip();
+
+    for (sender, inputs) in senders.iter().zip(self.inputs) {
+      for value in inputs {
+        sender.send(value).ok();
+      }
+    }
+
+    let mut nexts = match self.topology {
+      Topology::Pipeline => senders.drain(..).skip(1).map(Some).chain(Some(None)).collect::<Vec<_>>(),
+      Topology::Ring => {
+        senders.rotate_left(1);
+        senders.drain(..).map(Some).collect::<Vec<_>>()
+      },
+      Topology::Bus { .. } => unreachable!()
+    };
+
+    for (id, (mut machine, mut receiver)) in self.machines.into_iter().zip(receivers).enumerate() {
+      let mut link = Link { from: id, next: nexts[id].take(), events: event_sender.clone() };
+
+      thread::spawn(move || {
+        let event = match machine.run_with_io(&mut receiver, &mut link) {
+          Ok(ProgramResult::Halt) => NetworkEvent::Halt(id),
+          Ok(_) => return,
+          Err(err) => NetworkEvent::Crash(id, err)
+        };
+
+        link.events.send(event).ok();
+      });
+    }
+
+    event_receiver
+  }
+}
+
+#[cfg(test)]
+fn amplifier_program() -> Vec<i64> {
+  crate::assemble("
+          in -> [phase]
+    loop: in -> [x]
+          mul [x], #2 -> [x]
+          add [x], [phase] -> [x]
+          out [x]
+          lt [x], #1000 -> [cond]
+          jt [cond], #loop
+          hlt
+    phase: data 0
+    x:    data 0
+    cond: data 0
+  ").unwrap()
+}
+
+#[test]
+fn pipeline_and_ring() {
+  let program = amplifier_program();
+  let run = |topology| {
+    let machines = (0..3).map(|_| Machine::new(program.clone())).collect();
+    let mut network = Network::new(machines, topology);
+
+    for id in 0..3 {
+      network.send(id, id as i64 + 1);
+    }
+
+    network.send(0, 0);
+    network
+  };
+
+  let outputs = run(Topology::Pipeline)
+    .take_while(|event| *event != NetworkEvent::Idle)
+    .filter_map(|event| match event {
+      NetworkEvent::Output { from: 2, value } => Some(value),
+      _ => None
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(outputs, vec![11]);
+
+  let events = run(Topology::Ring).collect::<Vec<_>>();
+  let sequential = events.iter()
+    .filter(|event| matches!(event, NetworkEvent::Output { from: 2, .. }))
+    .cloned()
+    .collect::<Vec<_>>();
+
+  assert_eq!(sequential.last(), Some(&NetworkEvent::Output { from: 2, value: 6435 }));
+  assert_eq!(events.iter().filter(|event| matches!(event, NetworkEvent::Halt(_))).count(), 3);
+
+  let threaded = run(Topology::Ring).run_threaded()
+    .into_iter()
+    .filter(|event| matches!(event, NetworkEvent::Output { from: 2, .. }))
+    .collect::<Vec<_>>();
+
+  assert_eq!(threaded, sequential);
+}
+
+#[test]
+fn bus_with_nat() {
+  // Sends its input to the NAT once, then forwards every packet it gets
+  // back to the NAT with y incremented, until y reaches 3.
+  let program = crate::assemble("
+          in -> [y]
+          out #255
+          out #0
+          out [y]
+    loop: in -> [x]
+          eq [x], #-1 -> [cond]
+          jt [cond], #loop
+          in -> [y]
+          add [y], #1 -> [y]
+          lt [y], #3 -> [cond]
+          jf [cond], #end
+          out #255
+          out [x]
+          out [y]
+          jt #1, #loop
+    end:  hlt
+    x:    data 0
+    y:    data 0
+    cond: data 0
+  ").unwrap();
+
+  let mut network = Network::new(vec![Machine::new(program)], Topology::Bus { nat: Some(255) });
+  network.send(0, 0);
+
+  let packets = network
+    .filter_map(|event| match event {
+      NetworkEvent::Packet(packet) => Some((packet.from, packet.to, packet.y)),
+      _ => None
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(packets, vec![(0, 255, 0), (255, 0, 0), (0, 255, 1), (255, 0, 1), (0, 255, 2), (255, 0, 2)]);
+}
+
+#[test]
+fn threaded_bus() {
+  // NIC 0 starts a packet that every NIC forwards to the next one, adding
+  // its address to x and tripling y. The last NIC sends it to the NAT.
+  let program = crate::assemble("
+          in -> [addr]
+          jt [addr], #loop
+          out #1
+          out #0
+          out #1
+    loop: in -> [x]
+          eq [x], #-1 -> [cond]
+          jt [cond], #loop
+          in -> [y]
+          add [addr], #1 -> [next]
+          eq [next], #10 -> [cond]
+          jf [cond], #send
+          add #255, #0 -> [next]
+    send: out [next]
+          add [x], [addr] -> [x]
+          out [x]
+          mul [y], #3 -> [y]
+          out [y]
+          jt #1, #loop
+    addr: data 0
+    next: data 0
+    x:    data 0
+    y:    data 0
+    cond: data 0
+  ").unwrap();
+
+  let network = || {
+    let machines = (0..10).map(|_| Machine::new(program.clone())).collect();
+    let mut network = Network::new(machines, Topology::Bus { nat: Some(255) });
+
+    for id in 0..10 {
+      network.send(id, id as i64);
+    }
+
+    network
+  };
+
+  let nat_packets = |events: &mut dyn Iterator<Item = NetworkEvent>| {
+    events
+      .filter_map(|event| match event {
+        NetworkEvent::Packet(packet) if packet.to == 255 || packet.from == 255 => Some(packet),
+        _ => None
+      })
+      .take(3)
+      .collect::<Vec<_>>()
+  };
+
+  let sequential = nat_packets(&mut network());
+  let threaded = nat_packets(&mut network().run_threaded().into_iter());
+
+  assert_eq!(sequential[0], Packet { from: 9, to: 255, x: 45, y: 19683 });
+  assert_eq!(sequential[1], Packet { from: 255, to: 0, x: 45, y: 19683 });
+  assert_eq!(threaded, sequential);
+
+  let empty = Network::<Vec<i64>>::new(vec![], Topology::Bus { nat: Some(255) });
+  assert_eq!(empty.run_threaded().iter().count(), 0);
+}
+
+#[test]
+fn threaded_day23_network() {
+  let program = crate::parse_program(include_str!("../../23.txt"));
+
+  let network = || {
+    let machines = (0..50).map(|_| Machine::new(program.clone())).collect();
+    let mut network = Network::new(machines, Topology::Bus { nat: Some(255) });
+
+    for id in 0..50 {
+      network.send(id, id as i64);
+    }
+
+    network
+  };
+
+  // The packets the NAT gets in between depend on how the NICs are
+  // scheduled, but the first one and the ones it sends when the network
+  // is idle don't: those are the answers to both parts of the puzzle.
+  let nat_packets = |events: &mut dyn Iterator<Item = NetworkEvent>| {
+    let mut first = None;
+    let mut sent: Vec<Packet> = vec![];
+
+    for event in events {
+      match event {
+        NetworkEvent::Packet(packet) if packet.to == 255 => {
+          first = first.or(Some(packet));
+        },
+        NetworkEvent::Packet(packet) if packet.from == 255 => {
+          let repeated = sent.last().is_some_and(|last| last.y == packet.y);
+          sent.push(packet);
+
+          if repeated {
+            break;
+          }
+        },
+        NetworkEvent::Crash(id, err) => panic!("NIC {}: {}", id, err),
+        _ => {}
+      }
+    }
+
+    (first, sent)
+  };
+
+  let sequential = nat_packets(&mut network());
+  let threaded = nat_packets(&mut network().run_threaded().into_iter());
+
+  assert!(sequential.0.is_some() && sequential.1.len() > 1);
+  assert_eq!(threaded, sequential);
+}
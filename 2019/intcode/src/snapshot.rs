@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader, BufWriter};
+use std::path::Path;
+
+use crate::machine::Machine;
+use crate::memory::{Memory, PagedMemory};
+use crate::parse_program;
+
+const HEADER: &str = "intcode snapshot";
+
+/// Saved machine state. Taking a snapshot shares memory pages with the
+/// machine, so it's cheap to keep one around for every branch of a search.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+  pub memory: PagedMemory,
+  pub instruction_pointer: usize,
+  pub relative_base: i64,
+  pub memory_limit: usize
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+fn parse_field<T: std::str::FromStr>(line: Option<&str>, name: &str) -> io::Result<T> {
+  line.and_then(|line| line.strip_prefix(name))
+    .and_then(|value| value.trim().parse::<T>().ok())
+    .ok_or_else(|| invalid_data(&format!("missing or invalid {}", name)))
+}
+
+impl Snapshot {
+  pub fn to_machine(&self) -> Machine {
+    let mut machine = Machine::new(vec![]);
+    machine.restore(self);
+    machine
+  }
+
+  /// Writes the snapshot as a short text header followed by one line per
+  /// allocated memory page, starting with `@` and the page address followed
+  /// by its values in the usual comma separated program format.
+  pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+
+    writeln!(writer, "{}", HEADER)?;
+    writeln!(writer, "instruction_pointer {}", self.instruction_pointer)?;
+    writeln!(writer, "relative_base {}", self.relative_base)?;
+    writeln!(writer, "memory_limit {}", self.memory_limit)?;
+    writeln!(writer, "memory_length {}", self.memory.len())?;
+
+    for (start, values) in self.memory.pages() {
+      let len = values.iter().rposition(|&value| value != 0).map_or(0, |i| i + 1);
+      let values = values[..len].iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>();
+
+      writeln!(writer, "@{} {}", start, values.join(","))?;
+    }
+
+    writer.flush()
+  }
+
+  pub fn read_from<R: Read>(reader: R) -> io::Result<Snapshot> {
+    let mut contents = String::new();
+    BufReader::new(reader).read_to_string(&mut contents)?;
+
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(HEADER) {
+      return Err(invalid_data("not an intcode snapshot"));
+    }
+
+    let instruction_pointer = parse_field(lines.next(), "instruction_pointer")?;
+    let relative_base = parse_field(lines.next(), "relative_base")?;
+    let memory_limit = parse_field(lines.next(), "memory_limit")?;
+    let mut memory = PagedMemory::new();
+
+    memory.resize(parse_field(lines.next(), "memory_length")?);
+
+    for line in lines {
+      let (start, values) = line.strip_prefix('@')
+        .map(|line| line.split_at(line.find(' ').unwrap_or(line.len())))
+        .ok_or_else(|| invalid_data("invalid memory page"))?;
+      let start = start.parse::<usize>()
+        .map_err(|_| invalid_data("invalid memory page address"))?;
+
+      for (i, value) in parse_program(values).into_iter().enumerate() {
+        let address = start.checked_add(i)
+          .filter(|&address| address < memory_limit)
+          .ok_or_else(|| invalid_data("memory page beyond the memory limit"))?;
+
+        memory.write(address, value);
+      }
+    }
+
+    Ok(Snapshot { memory, instruction_pointer, relative_base, memory_limit })
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    self.write_to(File::create(path)?)
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Snapshot> {
+    Snapshot::read_from(File::open(path)?)
+  }
+}
+
+#[test]
+fn fork_and_restore() {
+  use crate::{run_program, run_program_with_inputs, ProgramResult};
+
+  let program = vec![3, 100, 1001, 100, 1, 100, 4, 100, 1105, 1, 0];
+  let mut machine = Machine::new(program);
+
+  assert_eq!(run_program(&mut machine, None), Ok(ProgramResult::WaitForInput));
+
+  let snapshot = machine.snapshot();
+  let mut fork = machine.fork();
+
+  assert_eq!(run_program_with_inputs(&mut machine, vec![1, 2].into_iter()).unwrap().0, vec![2, 3]);
+  assert_eq!(run_program_with_inputs(&mut fork, vec![10].into_iter()).unwrap().0, vec![11]);
+
+  machine.restore(&snapshot);
+
+  assert_eq!(run_program_with_inputs(&mut machine, vec![20].into_iter()).unwrap().0, vec![21]);
+
+  let mut serialized = vec![];
+  snapshot.write_to(&mut serialized).unwrap();
+  let mut loaded = Snapshot::read_from(&serialized[..]).unwrap().to_machine();
+
+  assert_eq!(loaded.memory.to_vec(), snapshot.memory.to_vec());
+  assert_eq!(run_program_with_inputs(&mut loaded, vec![30].into_iter()).unwrap().0, vec![31]);
+  assert!(Snapshot::read_from(&b"1,2,3"[..]).is_err());
+
+  let mut corrupt = String::from_utf8(serialized).unwrap();
+  corrupt.push_str(&format!("@{} 1,2\n", usize::MAX));
+
+  let err = Snapshot::read_from(corrupt.as_bytes()).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
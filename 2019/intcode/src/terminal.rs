@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::error::VmError;
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::{Memory, PagedMemory};
+
+#[derive(Debug)]
+pub enum TerminalError {
+  Vm(VmError),
+  Io(io::Error),
+  Parse { line: usize, message: String }
+}
+
+impl fmt::Display for TerminalError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TerminalError::Vm(err) => write!(f, "{}", err),
+      TerminalError::Io(err) => write!(f, "{}", err),
+      TerminalError::Parse { line, message } => write!(f, "line {}: {}", line, message)
+    }
+  }
+}
+
+impl std::error::Error for TerminalError {}
+
+impl From<VmError> for TerminalError {
+  fn from(err: VmError) -> TerminalError {
+    TerminalError::Vm(err)
+  }
+}
+
+impl From<io::Error> for TerminalError {
+  fn from(err: io::Error) -> TerminalError {
+    TerminalError::Io(err)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptEntry {
+  /// A line of program output. A prompt without a trailing newline is
+  /// recorded as a line of its own as soon as the program asks for input.
+  Output(String),
+  Input(String),
+  /// An output value outside of the ASCII range.
+  Value(i64)
+}
+
+/// Record of a terminal session. It's written as one entry per line,
+/// prefixed with `|` for output, `>` for input and `=` for values.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Transcript {
+  pub entries: Vec<TranscriptEntry>
+}
+
+impl Transcript {
+  pub fn inputs(&self) -> impl Iterator<Item = &str> {
+    self.entries.iter().filter_map(|entry| match entry {
+      TranscriptEntry::Input(line) => Some(line.as_str()),
+      _ => None
+    })
+  }
+
+  pub fn parse(input: &str) -> Result<Transcript, TerminalError> {
+    let entries = input.lines()
+      .enumerate()
+      .filter(|(_, line)| !line.is_empty())
+      .map(|(i, line)| {
+        let fail = |message: &str| TerminalError::Parse { line: i + 1, message: message.to_owned() };
+        let (prefix, rest) = line.split_at(line.char_indices().nth(1).map_or(line.len(), |(j, _)| j));
+        let rest = rest.strip_prefix(' ').unwrap_or(rest);
+
+        match prefix {
+          "|" => Ok(TranscriptEntry::Output(rest.to_owned())),
+          ">" => Ok(TranscriptEntry::Input(rest.to_owned())),
+          "=" => rest.parse::<i64>()
+            .map(TranscriptEntry::Value)
+            .map_err(|_| fail("invalid value")),
+          _ => Err(fail("expected '|', '>' or '='"))
+        }
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Transcript { entries })
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Transcript, TerminalError> {
+    Transcript::parse(&fs::read_to_string(path)?)
+  }
+
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    fs::write(path, self.to_string())
+  }
+}
+
+impl fmt::Display for Transcript {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for entry in &self.entries {
+      match entry {
+        TranscriptEntry::Output(line) => writeln!(f, "| {}", line)?,
+        TranscriptEntry::Input(line) => writeln!(f, "> {}", line)?,
+        TranscriptEntry::Value(value) => writeln!(f, "= {}", value)?
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Line based front-end for ASCII Intcode programs. Output is streamed as
+/// it's produced, input lines come from a script first and from the reader
+/// passed to `run` after that. Everything is recorded in a transcript.
+pub struct Terminal<M: Memory = PagedMemory> {
+  pub machine: Machine<M>,
+  pub transcript: Transcript,
+  script: VecDeque<String>,
+  pending: VecDeque<i64>,
+  line: String
+}
+
+impl<M: Memory> Terminal<M> {
+  pub fn new(machine: Machine<M>) -> Terminal<M> {
+    Terminal {
+      machine,
+      transcript: Transcript::default(),
+      script: VecDeque::new(),
+      pending: VecDeque::new(),
+      line: String::new()
+    }
+  }
+
+  /// Queues lines to be entered before reading from the terminal.
+  pub fn script<I, S>(&mut self, lines: I)
+  where I: IntoIterator<Item = S>, S: Into<String> {
+    self.script.extend(lines.into_iter().map(|line| line.into()));
+  }
+
+  pub fn load_script<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+    self.script(contents.lines());
+    Ok(())
+  }
+
+  fn end_line(&mut self) {
+    if !self.line.is_empty() {
+      let line = std::mem::take(&mut self.line);
+      self.transcript.entries.push(TranscriptEntry::Output(line));
+    }
+  }
+
+  fn write_output<W: Write>(&mut self, value: i64, output: &mut W) -> io::Result<()> {
+    match value {
+      10 => {
+        writeln!(output)?;
+
+        let line = std::mem::take(&mut self.line);
+        self.transcript.entries.push(TranscriptEntry::Output(line));
+      },
+      0..=127 => {
+        write!(output, "{}", value as u8 as char)?;
+        self.line.push(value as u8 as char);
+      },
+      _ => {
+        self.end_line();
+        writeln!(output, "{}", value)?;
+        self.transcript.entries.push(TranscriptEntry::Value(value));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn read_line<R: BufRead, W: Write>(&mut self, input: &mut R, output: &mut W) -> io::Result<Option<String>> {
+    if let Some(line) = self.script.pop_front() {
+      writeln!(output, "{}", line)?;
+      return Ok(Some(line));
+    }
+
+    output.flush()?;
+
+    let mut line = String::new();
+
+    if input.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+
+    Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).to_owned()))
+  }
+
+  /// Runs until the program halts or both the script and `input` are
+  /// exhausted while the program waits for input.
+  pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> Result<ProgramResult, TerminalError> {
+    let mut value = None;
+
+    loop {
+      match self.machine.step(&mut value)? {
+        None => {},
+        Some(ProgramResult::Output(x)) => self.write_output(x, &mut output)?,
+        Some(ProgramResult::WaitForInput) => {
+          if self.pending.is_empty() {
+            self.end_line();
+
+            match self.read_line(&mut input, &mut output)? {
+              Some(line) => {
+                self.pending.extend(line.chars().chain(Some('\n')).map(|c| c as i64));
+                self.transcript.entries.push(TranscriptEntry::Input(line));
+              },
+              None => {
+                output.flush()?;
+                return Ok(ProgramResult::WaitForInput);
+              }
+            }
+          }
+
+          value = self.pending.pop_front();
+        },
+        Some(ProgramResult::Halt) => {
+          self.end_line();
+          output.flush()?;
+          return Ok(ProgramResult::Halt);
+        }
+      }
+    }
+  }
+}
+
+/// Feeds the inputs of `transcript` to `machine` and returns the transcript
+/// of the new run, which equals the original if the run was reproduced.
+pub fn replay<M: Memory>(machine: Machine<M>, transcript: &Transcript) -> Result<Transcript, TerminalError> {
+  let mut terminal = Terminal::new(machine);
+
+  terminal.script(transcript.inputs());
+  terminal.run(io::empty(), io::sink())?;
+
+  Ok(terminal.transcript)
+}
+
+#[test]
+fn record_and_replay() {
+  use crate::assemble;
+
+  let program = assemble(r#"
+    loop: out #62
+          out #32
+          in -> [c]
+          eq [c], #113 -> [cond]
+          jt [cond], #end
+    echo: out [c]
+          eq [c], #10 -> [cond]
+          jt [cond], #loop
+          in -> [c]
+          jt #1, #echo
+    end:  out #4242
+          hlt
+    c:    data 0
+    cond: data 0
+  "#).unwrap();
+
+  let mut terminal = Terminal::new(Machine::new(program.clone()));
+  let mut output = vec![];
+
+  terminal.script(vec!["hello"]);
+
+  assert_eq!(terminal.run(&b"world\nq\n"[..], &mut output).unwrap(), ProgramResult::Halt);
+  assert_eq!(String::from_utf8(output).unwrap(), "> hello\nhello\n> world\n> 4242\n");
+
+  let transcript = terminal.transcript.to_string();
+  assert_eq!(transcript, "| > \n> hello\n| hello\n| > \n> world\n| world\n| > \n> q\n= 4242\n");
+
+  let parsed = Transcript::parse(&transcript).unwrap();
+  assert_eq!(parsed, terminal.transcript);
+  assert_eq!(replay(Machine::new(program), &parsed).unwrap(), parsed);
+}
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::VmError;
+use crate::instruction::{Instruction, OperationType, ParameterMode};
+use crate::machine::{Machine, ProgramResult};
+use crate::memory::Memory;
+use crate::io::{run_with_inputs, run_with_io, Input, Output};
+
+const HOT_ADDRESS_COUNT: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+  pub address: usize,
+  pub relative_base: i64,
+  pub instruction: Instruction,
+  pub values: Vec<i64>,
+  pub write: Option<(usize, i64)>
+}
+
+impl fmt::Display for TraceEntry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let values = self.values.iter()
+      .map(|value| value.to_string())
+      .collect::<Vec<_>>();
+
+    write!(f, "{:>6}  {:<32}{}", self.address, self.instruction.to_string(), values.join(", "))?;
+
+    if let Some((address, value)) = self.write {
+      write!(f, " -> [{}]={}", address, value)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+  pub instructions: u64,
+  pub operation_counts: HashMap<OperationType, u64>,
+  pub address_counts: HashMap<usize, u64>,
+  pub memory_high_water: usize
+}
+
+impl Profile {
+  /// The `n` most executed addresses, most executed first.
+  pub fn hot_addresses(&self, n: usize) -> Vec<(usize, u64)> {
+    let mut addresses = self.address_counts.iter()
+      .map(|(&address, &count)| (address, count))
+      .collect::<Vec<_>>();
+
+    addresses.sort_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+    addresses.truncate(n);
+    addresses
+  }
+}
+
+impl fmt::Display for Profile {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let percentage = |count: u64| 100.0 * count as f64 / self.instructions.max(1) as f64;
+    let mut operations = self.operation_counts.iter()
+      .map(|(&operation, &count)| (operation, count))
+      .collect::<Vec<_>>();
+
+    operations.sort_by_key(|&(operation, count)| (std::cmp::Reverse(count), operation.code()));
+
+    writeln!(f, "instructions: {}", self.instructions)?;
+    writeln!(f, "memory high-water mark: {}", self.memory_high_water)?;
+    writeln!(f, "operations:")?;
+
+    for (operation, count) in operations {
+      writeln!(f, "  {:<4}{:>12}{:>8.1}%", operation.mnemonic(), count, percentage(count))?;
+    }
+
+    writeln!(f, "hot addresses:")?;
+
+    for (address, count) in self.hot_addresses(HOT_ADDRESS_COUNT) {
+      writeln!(f, "  {:<6}{:>10}{:>8.1}%", address, count, percentage(count))?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Collects a profile and optionally a full instruction trace of the
+/// machines it runs. The same tracer can be used for several machines to
+/// profile a whole puzzle solution.
+#[derive(Debug, Clone, Default)]
+pub struct Tracer {
+  pub entries: Vec<TraceEntry>,
+  pub profile: Profile,
+  record_entries: bool
+}
+
+fn read<M: Memory>(machine: &Machine<M>, address: i64) -> i64 {
+  if address < 0 {
+    return 0;
+  }
+
+  machine.memory.read(address as usize)
+}
+
+fn resolve_address<M: Memory>(machine: &Machine<M>, (mode, value): (ParameterMode, i64)) -> i64 {
+  match mode {
+    ParameterMode::Relative => machine.relative_base.saturating_add(value),
+    _ => value
+  }
+}
+
+impl Tracer {
+  pub fn new(record_entries: bool) -> Tracer {
+    Tracer {
+      record_entries,
+      ..Tracer::default()
+    }
+  }
+
+  fn step<M: Memory>(&mut self, machine: &mut Machine<M>, input: &mut Option<i64>) -> Result<Option<ProgramResult>, VmError> {
+    let address = machine.instruction_pointer;
+
+    if address >= machine.memory.len() {
+      return machine.step(input);
+    }
+
+    let relative_base = machine.relative_base;
+    let instruction = machine.instruction_at(address)?;
+    let output_parameter = instruction.operation.output_parameter();
+    let mut values = vec![];
+
+    if self.record_entries {
+      values = instruction.inputs.iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != output_parameter)
+        .map(|(_, &parameter)| match parameter {
+          (ParameterMode::Immediate, value) => value,
+          _ => read(machine, resolve_address(machine, parameter))
+        })
+        .collect::<Vec<_>>();
+
+      if instruction.operation == OperationType::Input {
+        values.extend(*input);
+      }
+    }
+
+    let result = machine.step(input)?;
+
+    if result == Some(ProgramResult::WaitForInput) {
+      return Ok(result);
+    }
+
+    let profile = &mut self.profile;
+
+    profile.instructions += 1;
+    *profile.operation_counts.entry(instruction.operation).or_default() += 1;
+    *profile.address_counts.entry(address).or_default() += 1;
+    profile.memory_high_water = profile.memory_high_water.max(machine.memory.len());
+
+    if self.record_entries {
+      let write = output_parameter.map(|i| {
+        let target = resolve_address(machine, instruction.inputs[i]);
+        (target as usize, read(machine, target))
+      });
+
+      self.entries.push(TraceEntry { address, relative_base, instruction, values, write });
+    }
+
+    Ok(result)
+  }
+
+  /// Same as `run_program`, but records every executed instruction.
+  pub fn run_program<M: Memory>(&mut self, state: &mut Machine<M>, input: Option<i64>) -> Result<ProgramResult, VmError> {
+    let mut input = input;
+
+    loop {
+      if let Some(result) = self.step(state, &mut input)? {
+        return Ok(result);
+      }
+    }
+  }
+
+  pub fn run_program_with_inputs<M, I>(&mut self, state: &mut Machine<M>, inputs: I) -> Result<(Vec<i64>, ProgramResult), VmError>
+  where M: Memory, I: Iterator<Item = i64> {
+    run_with_inputs(state, inputs, |state, input| self.step(state, input))
+  }
+
+  pub fn run_with_io<M, I, O>(&mut self, state: &mut Machine<M>, input: I, output: O) -> Result<ProgramResult, VmError>
+  where M: Memory, I: Input, O: Output {
+    run_with_io(state, input, output, |state, input| self.step(state, input))
+  }
+
+  pub fn trace(&self) -> String {
+    self.entries.iter()
+      .map(|entry| format!("{}\n", entry))
+      .collect()
+  }
+}
+
+#[test]
+fn trace_and_profile() {
+  use crate::assemble;
+
+  let program = assemble("
+          in -> [x]
+    loop: mul [x], #2 -> [x]
+          lt [x], #20 -> [cond]
+          jt [cond], #loop
+          out [x]
+          hlt
+    x:    data 0
+    cond: data 0
+  ").unwrap();
+
+  let program_len = program.len();
+  let mut tracer = Tracer::new(true);
+  let (outputs, _) = tracer.run_program_with_inputs(&mut Machine::new(program), vec![3].into_iter()).unwrap();
+
+  assert_eq!(outputs, vec![24]);
+  assert_eq!(tracer.entries[0].to_string(), "     0  IN -> [16]                      3 -> [16]=3");
+  assert_eq!(tracer.entries[1].to_string(), "     2  MUL [16], #2 -> [16]            3, 2 -> [16]=6");
+  assert_eq!(tracer.profile.instructions, 12);
+  assert_eq!(tracer.profile.operation_counts[&OperationType::Multiply], 3);
+  assert_eq!(tracer.profile.hot_addresses(1), vec![(2, 3)]);
+  assert_eq!(tracer.profile.memory_high_water, program_len);
+}
@@ -0,0 +1,183 @@
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// A block letter font. Letters are placed `pitch` columns apart, so
+/// narrow letters are followed by blank columns and wide letters may touch
+/// the next one.
+pub struct Font {
+  pub width: usize,
+  pub height: usize,
+  pub pitch: usize,
+  pub glyphs: &'static [(char, &'static [&'static str])]
+}
+
+/// Font used by most displays since 2016, e.g. in 2019/08 and 2019/11.
+pub const FONT_4X6: Font = Font {
+  width: 4,
+  height: 6,
+  pitch: 5,
+  glyphs: &[
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &["###.", ".#..", ".#..", ".#..", ".#..", "###."]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', &["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"])
+  ]
+};
+
+/// Large font used by 2018/10.
+pub const FONT_6X10: Font = Font {
+  width: 6,
+  height: 10,
+  pitch: 8,
+  glyphs: &[
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"])
+  ]
+};
+
+pub const FONTS: &[&Font] = &[&FONT_4X6, &FONT_6X10];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OcrError {
+  Empty,
+  /// No font matches the height of the lit area.
+  UnknownHeight(usize),
+  /// Some glyphs are unknown. `text` holds `?` in their place and `glyphs`
+  /// their positions in the text with a rendering of each.
+  UnknownGlyphs { text: String, glyphs: Vec<(usize, String)> }
+}
+
+impl fmt::Display for OcrError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      OcrError::Empty => write!(f, "no lit points"),
+      OcrError::UnknownHeight(height) => write!(f, "no font with a height of {}", height),
+      OcrError::UnknownGlyphs { text, glyphs } => {
+        write!(f, "unrecognized glyphs in '{}'", text)?;
+
+        for (i, glyph) in glyphs {
+          write!(f, "\nglyph {}:\n{}", i + 1, glyph)?;
+        }
+
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Reads the text spelled by `points`. The text may be anywhere, only its
+/// bounding box matters.
+pub fn recognize<I: IntoIterator<Item = (i64, i64)>>(points: I) -> Result<String, OcrError> {
+  let points = points.into_iter().collect::<HashSet<_>>();
+  let min_x = points.iter().map(|p| p.0).min().ok_or(OcrError::Empty)?;
+  let max_x = points.iter().map(|p| p.0).max().ok_or(OcrError::Empty)?;
+  let min_y = points.iter().map(|p| p.1).min().ok_or(OcrError::Empty)?;
+  let max_y = points.iter().map(|p| p.1).max().ok_or(OcrError::Empty)?;
+  let height = (max_y - min_y + 1) as usize;
+  let width = (max_x - min_x + 1) as usize;
+
+  let font = FONTS.iter()
+    .find(|font| font.height == height)
+    .ok_or(OcrError::UnknownHeight(height))?;
+
+  let mut text = String::new();
+  let mut unknown = vec![];
+
+  for i in 0..width.div_ceil(font.pitch) {
+    let left = min_x + (i * font.pitch) as i64;
+    let rows = (0..font.height)
+      .map(|dy| (0..font.width)
+        .map(|dx| if points.contains(&(left + dx as i64, min_y + dy as i64)) { '#' } else { '.' })
+        .collect::<String>())
+      .collect::<Vec<_>>();
+
+    let letter = font.glyphs.iter()
+      .find(|(_, glyph)| glyph.iter().eq(rows.iter()))
+      .map(|&(c, _)| c);
+
+    match letter {
+      Some(c) => text.push(c),
+      None => {
+        text.push('?');
+        unknown.push((i, rows.join("\n")));
+      }
+    }
+  }
+
+  if unknown.is_empty() {
+    Ok(text)
+  } else {
+    Err(OcrError::UnknownGlyphs { text, glyphs: unknown })
+  }
+}
+
+/// Lights the glyphs of `text` starting at `origin`, drawing characters
+/// missing from the font as filled boxes.
+#[cfg(test)]
+fn draw(font: &Font, text: &str, origin: (i64, i64)) -> Vec<(i64, i64)> {
+  let filled = vec!["#".repeat(font.width); font.height];
+  let mut points = vec![];
+
+  for (i, c) in text.chars().enumerate() {
+    let rows = font.glyphs.iter()
+      .find(|&&(glyph, _)| glyph == c)
+      .map_or(filled.clone(), |(_, rows)| rows.iter().map(|row| row.to_string()).collect());
+
+    for (dy, row) in rows.iter().enumerate() {
+      for (dx, _) in row.char_indices().filter(|&(_, c)| c == '#') {
+        points.push((origin.0 + (i * font.pitch + dx) as i64, origin.1 + dy as i64));
+      }
+    }
+  }
+
+  points
+}
+
+#[test]
+fn recognize_fonts() {
+  assert_eq!(recognize(draw(&FONT_4X6, "HELLO", (-7, 3))), Ok("HELLO".to_owned()));
+  assert_eq!(recognize(draw(&FONT_4X6, "J", (0, 0))), Ok("J".to_owned()));
+  assert_eq!(recognize(draw(&FONT_6X10, "BLANK", (12, -40))), Ok("BLANK".to_owned()));
+}
+
+#[test]
+fn recognize_errors() {
+  assert_eq!(recognize(vec![]), Err(OcrError::Empty));
+  assert_eq!(recognize(vec![(3, 4), (5, 4)]), Err(OcrError::UnknownHeight(1)));
+  assert_eq!(recognize(draw(&FONT_4X6, "HIGH", (0, 0)).into_iter().filter(|p| p.1 < 4)), Err(OcrError::UnknownHeight(4)));
+
+  let err = recognize(draw(&FONT_4X6, "H#L", (2, 2))).unwrap_err();
+  let filled = ["####"; 6].join("\n");
+
+  assert_eq!(err, OcrError::UnknownGlyphs { text: "H?L".to_owned(), glyphs: vec![(1, filled.clone())] });
+  assert_eq!(err.to_string(), format!("unrecognized glyphs in 'H?L'\nglyph 2:\n{}", filled));
+}
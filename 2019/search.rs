@@ -0,0 +1,564 @@
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+
+/// A search problem over implicitly defined states, e.g. game states.
+pub trait Problem {
+  type State: Clone;
+  /// Identifies states that are interchangeable for the search. Problems
+  /// that don't deduplicate states can use `()`.
+  type Key: Hash + Eq;
+
+  /// Returns the states reachable in one step along with their costs.
+  fn successors(&self, state: &Self::State) -> Vec<(usize, Self::State)>;
+
+  fn is_goal(&self, state: &Self::State) -> bool;
+
+  /// Estimates the remaining cost to a goal. Must never be too high for
+  /// `a_star` and `branch_and_bound` to find the cheapest solution.
+  fn heuristic(&self, _state: &Self::State) -> usize {
+    0
+  }
+
+  /// Returns a key for deduplicating `state`, or `None` if it shouldn't be
+  /// deduplicated.
+  fn canonical_key(&self, _state: &Self::State) -> Option<Self::Key> {
+    None
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Statistics {
+  /// States whose successors were generated.
+  pub expanded: usize,
+  pub generated: usize,
+  /// States dropped because they couldn't beat the best known solution or
+  /// didn't fit into the beam.
+  pub pruned: usize,
+  /// States dropped because an equivalent state was reached as cheaply.
+  pub duplicates: usize
+}
+
+impl fmt::Display for Statistics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f, "{} expanded, {} generated, {} pruned, {} duplicates",
+      self.expanded, self.generated, self.pruned, self.duplicates
+    )
+  }
+}
+
+/// A path of states from the start to a goal and its total cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution<S> {
+  pub cost: usize,
+  pub path: Vec<S>
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome<S> {
+  pub solution: Option<Solution<S>>,
+  pub statistics: Statistics
+}
+
+/// States discovered so far, each pointing to its predecessor.
+struct Arena<S> {
+  nodes: Vec<(S, Option<usize>, usize)>
+}
+
+impl<S: Clone> Arena<S> {
+  fn push(&mut self, state: S, parent: Option<usize>, cost: usize) -> usize {
+    self.nodes.push((state, parent, cost));
+    self.nodes.len() - 1
+  }
+
+  fn solution(&self, index: usize) -> Solution<S> {
+    let mut path = vec![];
+    let mut node = Some(index);
+
+    while let Some(i) = node {
+      path.push(self.nodes[i].0.clone());
+      node = self.nodes[i].1;
+    }
+
+    path.reverse();
+    Solution { cost: self.nodes[index].2, path }
+  }
+}
+
+/// Remembers the cheapest cost each canonical key was reached with.
+struct Seen<K> {
+  costs: HashMap<K, usize>
+}
+
+impl<K: Hash + Eq> Seen<K> {
+  /// Returns `false` if an equivalent state was already reached as cheaply.
+  fn visit(&mut self, key: Option<K>, cost: usize) -> bool {
+    match key {
+      Some(key) => match self.costs.get(&key) {
+        Some(&known) if known <= cost => false,
+        _ => {
+          self.costs.insert(key, cost);
+          true
+        }
+      },
+      None => true
+    }
+  }
+}
+
+struct HeapEntry {
+  priority: usize,
+  index: usize
+}
+
+impl PartialEq for HeapEntry {
+  fn eq(&self, other: &Self) -> bool {
+    self.priority == other.priority
+  }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for HeapEntry {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // Reversed, so the binary heap pops the lowest priority first
+    other.priority.cmp(&self.priority)
+  }
+}
+
+/// Expands states in order of cost plus heuristic and returns the first
+/// goal reached, which is a cheapest one if the heuristic is admissible.
+pub fn a_star<P: Problem>(problem: &P, start: P::State) -> Outcome<P::State> {
+  let mut statistics = Statistics::default();
+  let mut arena = Arena { nodes: vec![] };
+  let mut seen = Seen { costs: HashMap::new() };
+  let mut heap = BinaryHeap::new();
+
+  seen.visit(problem.canonical_key(&start), 0);
+  heap.push(HeapEntry { priority: problem.heuristic(&start), index: 0 });
+  arena.push(start, None, 0);
+
+  while let Some(HeapEntry { index, .. }) = heap.pop() {
+    let (state, _, cost) = arena.nodes[index].clone();
+
+    // Skip stale entries of states that were reached more cheaply later
+    if let Some(key) = problem.canonical_key(&state) {
+      if seen.costs.get(&key).is_some_and(|&known| known < cost) {
+        continue;
+      }
+    }
+
+    if problem.is_goal(&state) {
+      return Outcome { solution: Some(arena.solution(index)), statistics };
+    }
+
+    statistics.expanded += 1;
+
+    for (step, next) in problem.successors(&state) {
+      let next_cost = cost + step;
+      statistics.generated += 1;
+
+      if !seen.visit(problem.canonical_key(&next), next_cost) {
+        statistics.duplicates += 1;
+        continue;
+      }
+
+      let priority = next_cost + problem.heuristic(&next);
+      let next_index = arena.push(next, Some(index), next_cost);
+
+      heap.push(HeapEntry { priority, index: next_index });
+    }
+  }
+
+  Outcome { solution: None, statistics }
+}
+
+/// Searches depth-first, pruning states that can't beat the cheapest
+/// solution found so far, until the whole state space is covered. The
+/// state space must not contain cycles of cost zero.
+pub fn branch_and_bound<P: Problem>(problem: &P, start: P::State) -> Outcome<P::State> {
+  let mut statistics = Statistics::default();
+  let mut arena = Arena { nodes: vec![] };
+  let mut seen = Seen { costs: HashMap::new() };
+  let mut best: Option<(usize, usize)> = None;
+
+  seen.visit(problem.canonical_key(&start), 0);
+
+  let mut stack = vec![arena.push(start, None, 0)];
+
+  while let Some(index) = stack.pop() {
+    let (state, _, cost) = arena.nodes[index].clone();
+
+    if best.is_some_and(|(bound, _)| cost + problem.heuristic(&state) >= bound) {
+      statistics.pruned += 1;
+      continue;
+    }
+
+    if problem.is_goal(&state) {
+      best = Some((cost, index));
+      continue;
+    }
+
+    statistics.expanded += 1;
+
+    let mut successors = problem.successors(&state).into_iter()
+      .map(|(step, next)| (cost + step, next))
+      .collect::<Vec<_>>();
+
+    // Push the most promising successor last, so it's explored first
+    successors.sort_by_key(|(next_cost, next)| std::cmp::Reverse(next_cost + problem.heuristic(next)));
+
+    for (next_cost, next) in successors {
+      statistics.generated += 1;
+
+      if !seen.visit(problem.canonical_key(&next), next_cost) {
+        statistics.duplicates += 1;
+        continue;
+      }
+
+      stack.push(arena.push(next, Some(index), next_cost));
+    }
+  }
+
+  Outcome { solution: best.map(|(_, index)| arena.solution(index)), statistics }
+}
+
+/// Searches breadth-first, keeping only the `width` most promising states
+/// of each layer by cost plus heuristic. Fast, but may miss the cheapest
+/// solution or any solution at all.
+pub fn beam_search<P: Problem>(problem: &P, start: P::State, width: usize) -> Outcome<P::State> {
+  let mut statistics = Statistics::default();
+  let mut arena = Arena { nodes: vec![] };
+  let mut seen = Seen { costs: HashMap::new() };
+  let mut best: Option<(usize, usize)> = None;
+
+  seen.visit(problem.canonical_key(&start), 0);
+
+  let mut layer = vec![arena.push(start, None, 0)];
+
+  while !layer.is_empty() {
+    let mut next_layer = vec![];
+
+    for index in layer {
+      let (state, _, cost) = arena.nodes[index].clone();
+
+      if problem.is_goal(&state) {
+        if best.is_none_or(|(bound, _)| cost < bound) {
+          best = Some((cost, index));
+        }
+
+        continue;
+      }
+
+      statistics.expanded += 1;
+
+      for (step, next) in problem.successors(&state) {
+        let next_cost = cost + step;
+        statistics.generated += 1;
+
+        if best.is_some_and(|(bound, _)| next_cost + problem.heuristic(&next) >= bound) {
+          statistics.pruned += 1;
+          continue;
+        } else if !seen.visit(problem.canonical_key(&next), next_cost) {
+          statistics.duplicates += 1;
+          continue;
+        }
+
+        next_layer.push((next_cost + problem.heuristic(&next), arena.push(next, Some(index), next_cost)));
+      }
+    }
+
+    next_layer.sort_by_key(|&(priority, _)| priority);
+
+    if next_layer.len() > width {
+      statistics.pruned += next_layer.len() - width;
+      next_layer.truncate(width);
+    }
+
+    layer = next_layer.into_iter().map(|(_, index)| index).collect();
+  }
+
+  Outcome { solution: best.map(|(_, index)| arena.solution(index)), statistics }
+}
+
+/// A state `cost_to_goal` is exploring the successors of.
+struct Frame<S, K> {
+  key: Option<K>,
+  /// Cost of the step from the state below on the stack.
+  step: usize,
+  successors: std::vec::IntoIter<(usize, S)>,
+  cost: Option<usize>,
+  /// Lowest stack depth of a state whose cycle was cut below this one.
+  low: usize
+}
+
+/// Cheapest cost from `state` to a goal, memoized by canonical key. States
+/// on the current path count as dead ends, so cycles are cut. A cost that
+/// relied on cutting a cycle back to a state further down the stack only
+/// holds for the current path, so it isn't memoized.
+fn cost_to_goal<P: Problem>(
+  problem: &P,
+  state: &P::State,
+  memo: &mut HashMap<P::Key, Option<usize>>,
+  statistics: &mut Statistics
+) -> Option<usize> {
+  let mut stack: Vec<Frame<P::State, P::Key>> = vec![];
+  let mut on_path = HashMap::new();
+  let mut next = Some((0, state.clone()));
+
+  loop {
+    let depth = stack.len();
+
+    // Cost of the state just entered or finished and the depth it relies on
+    let (step, cost, low) = match next.take() {
+      Some((step, state)) => {
+        let key = problem.canonical_key(&state);
+
+        if let Some(&cost) = key.as_ref().and_then(|key| memo.get(key)) {
+          statistics.duplicates += 1;
+          (step, cost, depth)
+        } else if let Some(&ancestor) = key.as_ref().and_then(|key| on_path.get(key)) {
+          (step, None, ancestor)
+        } else if problem.is_goal(&state) {
+          if let Some(key) = key {
+            memo.insert(key, Some(0));
+          }
+
+          (step, Some(0), depth)
+        } else {
+          statistics.expanded += 1;
+
+          if let Some(key) = problem.canonical_key(&state) {
+            on_path.insert(key, depth);
+          }
+
+          let successors = problem.successors(&state).into_iter();
+          stack.push(Frame { key, step, successors, cost: None, low: depth });
+          continue;
+        }
+      },
+      None => {
+        if let Some(successor) = stack.last_mut().and_then(|frame| frame.successors.next()) {
+          statistics.generated += 1;
+          next = Some(successor);
+          continue;
+        }
+
+        let frame = stack.pop().expect("the search ends with the start state");
+        let depth = stack.len();
+
+        if let Some(key) = frame.key {
+          on_path.remove(&key);
+
+          if frame.low >= depth {
+            memo.insert(key, frame.cost);
+          }
+        }
+
+        (frame.step, frame.cost, frame.low)
+      }
+    };
+
+    match stack.last_mut() {
+      Some(frame) => {
+        frame.cost = frame.cost.into_iter().chain(cost.map(|cost| step + cost)).min();
+        frame.low = frame.low.min(low);
+      },
+      None => return cost
+    }
+  }
+}
+
+/// Computes the cheapest cost to a goal depth-first for every state,
+/// reusing results for states with the same canonical key. Suited to state
+/// spaces with lots of shared substructure and few cycles, as states on a
+/// cycle are explored again from every state leading into it. The state
+/// space must not contain cycles of cost zero.
+pub fn memoized_dfs<P: Problem>(problem: &P, start: P::State) -> Outcome<P::State> {
+  let mut statistics = Statistics::default();
+  let mut memo = HashMap::new();
+  let cost = match cost_to_goal(problem, &start, &mut memo, &mut statistics) {
+    Some(cost) => cost,
+    None => return Outcome { solution: None, statistics }
+  };
+
+  // Follow successors whose cost to goal accounts for the remaining cost
+  let mut path = vec![start];
+  let mut remaining = cost;
+
+  while !problem.is_goal(path.last().unwrap()) {
+    let next = problem.successors(path.last().unwrap()).into_iter()
+      .find(|(step, next)| {
+        *step <= remaining
+        && cost_to_goal(problem, next, &mut memo, &mut statistics) == Some(remaining - step)
+      });
+
+    match next {
+      Some((step, next)) => {
+        remaining -= step;
+        path.push(next);
+      },
+      None => return Outcome { solution: None, statistics }
+    }
+  }
+
+  Outcome { solution: Some(Solution { cost, path }), statistics }
+}
+
+/// Change for `amount` out of coins worth 1, 5, 6 and 9, counting coins.
+/// Greedily taking the largest coin fails for 11 = 5 + 6.
+struct Change {
+  amount: usize
+}
+
+#[cfg(test)]
+impl Problem for Change {
+  type State = usize;
+  type Key = usize;
+
+  fn successors(&self, &paid: &usize) -> Vec<(usize, usize)> {
+    [9, 6, 5, 1].iter()
+      .filter(|&&coin| paid + coin <= self.amount)
+      .map(|&coin| (1, paid + coin))
+      .collect()
+  }
+
+  fn is_goal(&self, &paid: &usize) -> bool {
+    paid == self.amount
+  }
+
+  fn heuristic(&self, &paid: &usize) -> usize {
+    (self.amount - paid).div_ceil(9)
+  }
+
+  fn canonical_key(&self, &paid: &usize) -> Option<usize> {
+    Some(paid)
+  }
+}
+
+/// A problem given by its edges between states named by letters, `G`
+/// being the goal.
+#[cfg(test)]
+struct Edges(Vec<(char, usize, char)>);
+
+#[cfg(test)]
+impl Problem for Edges {
+  type State = char;
+  type Key = char;
+
+  fn successors(&self, &state: &char) -> Vec<(usize, char)> {
+    self.0.iter()
+      .filter(|&&(from, _, _)| from == state)
+      .map(|&(_, cost, to)| (cost, to))
+      .collect()
+  }
+
+  fn is_goal(&self, &state: &char) -> bool {
+    state == 'G'
+  }
+
+  fn canonical_key(&self, &state: &char) -> Option<char> {
+    Some(state)
+  }
+}
+
+#[test]
+fn strategies_agree() {
+  let problem = Change { amount: 11 };
+
+  for outcome in [
+    a_star(&problem, 0),
+    branch_and_bound(&problem, 0),
+    beam_search(&problem, 0, 10),
+    memoized_dfs(&problem, 0)
+  ] {
+    let solution = outcome.solution.unwrap();
+
+    assert_eq!(solution.cost, 2);
+    assert_eq!(solution.path.first(), Some(&0));
+    assert_eq!(solution.path.last(), Some(&11));
+    assert!(outcome.statistics.expanded > 0);
+  }
+
+  assert_eq!(beam_search(&problem, 0, 1).solution.unwrap().cost, 3);
+  assert_eq!(a_star(&Change { amount: 0 }, 0).statistics.expanded, 0);
+}
+
+#[test]
+fn deduplication_saves_work() {
+  struct Undeduplicated(Change);
+
+  impl Problem for Undeduplicated {
+    type State = usize;
+    type Key = ();
+
+    fn successors(&self, state: &usize) -> Vec<(usize, usize)> {
+      self.0.successors(state)
+    }
+
+    fn is_goal(&self, state: &usize) -> bool {
+      self.0.is_goal(state)
+    }
+  }
+
+  let deduplicated = a_star(&Change { amount: 40 }, 0);
+  let undeduplicated = a_star(&Undeduplicated(Change { amount: 40 }), 0);
+
+  assert_eq!(deduplicated.solution.map(|s| s.cost), Some(6));
+  assert_eq!(undeduplicated.solution.map(|s| s.cost), Some(6));
+  assert!(deduplicated.statistics.expanded < undeduplicated.statistics.expanded);
+  assert!(deduplicated.statistics.duplicates > 0);
+}
+
+#[test]
+fn branch_and_bound_pruning() {
+  // Once S-A-G is found, B can't lead to anything cheaper
+  let problem = Edges(vec![('S', 1, 'A'), ('S', 5, 'B'), ('A', 1, 'G'), ('B', 1, 'C'), ('C', 1, 'G')]);
+  let outcome = branch_and_bound(&problem, 'S');
+
+  assert_eq!(outcome.solution, Some(Solution { cost: 2, path: vec!['S', 'A', 'G'] }));
+  assert_eq!(outcome.statistics.expanded, 2);
+  assert_eq!(outcome.statistics.pruned, 1);
+}
+
+#[test]
+fn beam_width_truncation() {
+  // The cheaper first step leads to the expensive solution
+  let problem = Edges(vec![('S', 1, 'A'), ('S', 2, 'B'), ('A', 10, 'G'), ('B', 1, 'G')]);
+  let narrow = beam_search(&problem, 'S', 1);
+  let wide = beam_search(&problem, 'S', 2);
+
+  assert_eq!(narrow.solution, Some(Solution { cost: 11, path: vec!['S', 'A', 'G'] }));
+  assert_eq!(narrow.statistics.pruned, 1);
+  assert_eq!(wide.solution, Some(Solution { cost: 3, path: vec!['S', 'B', 'G'] }));
+  assert_eq!(wide.statistics.pruned, 0);
+}
+
+#[test]
+fn memoized_dfs_with_cycles() {
+  // Y is first reached from X, where cutting the cycle back to X leaves it
+  // only its expensive edge to G. From S, Y can still go through X.
+  let problem = Edges(vec![
+    ('S', 100, 'X'), ('S', 1, 'Y'),
+    ('X', 1, 'Y'), ('X', 1, 'G'),
+    ('Y', 1, 'X'), ('Y', 10, 'G')
+  ]);
+
+  let expected = Some(Solution { cost: 3, path: vec!['S', 'Y', 'X', 'G'] });
+
+  assert_eq!(memoized_dfs(&problem, 'S').solution, expected);
+  assert_eq!(a_star(&problem, 'S').solution, expected);
+
+  let unreachable = Edges(vec![('S', 1, 'A'), ('A', 1, 'S')]);
+  assert_eq!(memoized_dfs(&unreachable, 'S').solution, None);
+}
@@ -0,0 +1,455 @@
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+pub const MAX_INSTRUCTIONS: usize = 15;
+pub const JUMP_DISTANCE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+  /// Ground sensor, `Sensor(0)` is `A` looking one tile ahead.
+  Sensor(usize),
+  T,
+  J
+}
+
+impl Register {
+  fn parse(input: &str) -> Option<Register> {
+    match input {
+      "T" => Some(Register::T),
+      "J" => Some(Register::J),
+      _ => {
+        let mut chars = input.chars();
+
+        match (chars.next(), chars.next()) {
+          (Some(c @ 'A'..='I'), None) => Some(Register::Sensor(c as usize - 'A' as usize)),
+          _ => None
+        }
+      }
+    }
+  }
+
+  pub fn is_writable(self) -> bool {
+    !matches!(self, Register::Sensor(_))
+  }
+}
+
+impl fmt::Display for Register {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Register::Sensor(i) => write!(f, "{}", (b'A' + *i as u8) as char),
+      Register::T => write!(f, "T"),
+      Register::J => write!(f, "J")
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+  And,
+  Or,
+  Not
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction {
+  pub operation: Operation,
+  pub source: Register,
+  pub target: Register
+}
+
+impl Instruction {
+  pub fn apply(self, source: bool, target: bool) -> bool {
+    match self.operation {
+      Operation::And => source && target,
+      Operation::Or => source || target,
+      Operation::Not => !source
+    }
+  }
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let operation = match self.operation {
+      Operation::And => "AND",
+      Operation::Or => "OR",
+      Operation::Not => "NOT"
+    };
+
+    write!(f, "{} {} {}", operation, self.source, self.target)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+  Walk,
+  Run
+}
+
+impl Mode {
+  /// Number of ground sensors available in this mode.
+  pub fn sensors(self) -> usize {
+    match self {
+      Mode::Walk => 4,
+      Mode::Run => 9
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+  Syntax { line: usize, text: String },
+  MissingMode,
+  TooLong(usize),
+  ReadOnlyTarget(Instruction),
+  SensorOutOfRange(Instruction, Mode)
+}
+
+impl fmt::Display for ScriptError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ScriptError::Syntax { line, text } => write!(f, "line {}: invalid instruction '{}'", line, text),
+      ScriptError::MissingMode => write!(f, "script does not end with WALK or RUN"),
+      ScriptError::TooLong(len) => write!(f, "{} instructions exceed the limit of {}", len, MAX_INSTRUCTIONS),
+      ScriptError::ReadOnlyTarget(instruction) => write!(f, "'{}' writes to a sensor", instruction),
+      ScriptError::SensorOutOfRange(instruction, mode) => write!(f, "'{}' reads a sensor unavailable in {:?} mode", instruction, mode)
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+  pub instructions: Vec<Instruction>,
+  pub mode: Mode
+}
+
+/// Sensor readings at `position` as a bit mask, bit `i` being set if sensor
+/// `i` sees ground. Everything past the end of the hull is ground.
+fn sense(hull: &[bool], position: usize, sensors: usize) -> u16 {
+  (0..sensors)
+    .filter(|i| hull.get(position + i + 1).cloned().unwrap_or(true))
+    .fold(0, |mask, i| mask | 1 << i)
+}
+
+/// Walks the droid across `hull` and returns the position it falls at.
+fn cross<F: FnMut(u16) -> bool>(hull: &[bool], sensors: usize, mut jumps: F) -> Result<(), usize> {
+  let mut position = 0;
+
+  while position < hull.len() {
+    if !hull[position] {
+      return Err(position);
+    }
+
+    position += if jumps(sense(hull, position, sensors)) { JUMP_DISTANCE } else { 1 };
+  }
+
+  Ok(())
+}
+
+impl Script {
+  pub fn new(instructions: Vec<Instruction>, mode: Mode) -> Script {
+    Script { instructions, mode }
+  }
+
+  /// Parses a script, ignoring blank lines and `#` comments, and validates it.
+  pub fn parse(input: &str) -> Result<Script, ScriptError> {
+    let mut instructions = vec![];
+    let mut mode = None;
+
+    for (i, line) in input.lines().enumerate() {
+      let line = line.trim();
+      let syntax_error = || ScriptError::Syntax { line: i + 1, text: line.to_owned() };
+
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      } else if mode.is_some() {
+        return Err(syntax_error());
+      }
+
+      let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+      let operation = match tokens[0] {
+        "WALK" if tokens.len() == 1 => {
+          mode = Some(Mode::Walk);
+          continue;
+        },
+        "RUN" if tokens.len() == 1 => {
+          mode = Some(Mode::Run);
+          continue;
+        },
+        "AND" => Operation::And,
+        "OR" => Operation::Or,
+        "NOT" => Operation::Not,
+        _ => return Err(syntax_error())
+      };
+
+      match tokens[1..] {
+        [source, target] => instructions.push(Instruction {
+          operation,
+          source: Register::parse(source).ok_or_else(syntax_error)?,
+          target: Register::parse(target).ok_or_else(syntax_error)?
+        }),
+        _ => return Err(syntax_error())
+      }
+    }
+
+    let script = Script::new(instructions, mode.ok_or(ScriptError::MissingMode)?);
+
+    script.validate()?;
+    Ok(script)
+  }
+
+  /// Checks the rules the springdroid enforces: at most `MAX_INSTRUCTIONS`
+  /// instructions, only `T` and `J` as targets, and no sensors beyond `D`
+  /// when walking.
+  pub fn validate(&self) -> Result<(), ScriptError> {
+    if self.instructions.len() > MAX_INSTRUCTIONS {
+      return Err(ScriptError::TooLong(self.instructions.len()));
+    }
+
+    for &instruction in &self.instructions {
+      if !instruction.target.is_writable() {
+        return Err(ScriptError::ReadOnlyTarget(instruction));
+      }
+
+      if let Register::Sensor(i) = instruction.source {
+        if i >= self.mode.sensors() {
+          return Err(ScriptError::SensorOutOfRange(instruction, self.mode));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Evaluates the script for the given sensor readings, see `sense`.
+  pub fn jumps(&self, sensors: u16) -> bool {
+    let (mut t, mut j) = (false, false);
+
+    for instruction in &self.instructions {
+      let source = match instruction.source {
+        Register::Sensor(i) => sensors & 1 << i != 0,
+        Register::T => t,
+        Register::J => j
+      };
+
+      match instruction.target {
+        Register::T => t = instruction.apply(source, t),
+        _ => j = instruction.apply(source, j)
+      }
+    }
+
+    j
+  }
+
+  /// Runs the droid across `hull`, where `true` is ground and the droid
+  /// starts at position 0. Returns the position it falls at, if any.
+  pub fn simulate(&self, hull: &[bool]) -> Result<(), usize> {
+    cross(hull, self.mode.sensors(), |sensors| self.jumps(sensors))
+  }
+}
+
+impl fmt::Display for Script {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for instruction in &self.instructions {
+      writeln!(f, "{}", instruction)?;
+    }
+
+    match self.mode {
+      Mode::Walk => writeln!(f, "WALK"),
+      Mode::Run => writeln!(f, "RUN")
+    }
+  }
+}
+
+/// Extracts the hull the droid fell into from the springdroid's output.
+pub fn parse_hull(output: &str) -> Option<Vec<bool>> {
+  output.lines()
+    .find(|line| line.contains('#') && line.chars().all(|c| c == '#' || c == '.'))
+    .map(|line| line.chars().map(|c| c == '#').collect())
+}
+
+/// Searches breadth-first for a shortest script with at most `max_len`
+/// instructions that gets the droid across all `hulls`. Registers are
+/// represented by their values in every sensor situation occurring on the
+/// hulls, so scripts computing the same values are only explored once.
+/// Gives up after visiting `limit` distinct register states.
+pub fn synthesize(hulls: &[Vec<bool>], mode: Mode, max_len: usize, limit: usize) -> Option<Script> {
+  let sensors = mode.sensors();
+  let mut situations = HashMap::new();
+
+  for hull in hulls {
+    for position in 0..hull.len() {
+      let len = situations.len();
+      situations.entry(sense(hull, position, sensors)).or_insert(len);
+    }
+  }
+
+  let words = situations.len().div_ceil(64);
+  let padding = match situations.len() % 64 {
+    0 => !0,
+    n => (1 << n) - 1
+  };
+  let mut sensor_values = vec![vec![0u64; words]; sensors];
+
+  for (&mask, &k) in &situations {
+    for (i, value) in sensor_values.iter_mut().enumerate() {
+      if mask & 1 << i != 0 {
+        value[k / 64] |= 1 << (k % 64);
+      }
+    }
+  }
+
+  let registers = (0..sensors).map(Register::Sensor)
+    .chain(vec![Register::T, Register::J])
+    .collect::<Vec<_>>();
+  let candidates = [Operation::And, Operation::Or, Operation::Not].iter()
+    .flat_map(|&operation| registers.iter().flat_map(move |&source| {
+      vec![Register::T, Register::J].into_iter()
+        .map(move |target| Instruction { operation, source, target })
+    }))
+    .collect::<Vec<_>>();
+
+  let is_solution = |j: &[u64]| hulls.iter().all(|hull| {
+    cross(hull, sensors, |mask| {
+      let k = situations[&mask];
+      j[k / 64] & 1 << (k % 64) != 0
+    }).is_ok()
+  });
+
+  // A state holds the values of T followed by the values of J
+  let start = vec![0u64; 2 * words];
+  let mut visited = HashSet::new();
+  let mut nodes: Vec<(Option<usize>, Instruction)> = vec![];
+  let mut frontier = vec![(start.clone(), None)];
+
+  visited.insert(start);
+
+  for depth in 0..=max_len {
+    for (state, node) in &frontier {
+      if is_solution(&state[words..]) {
+        let mut instructions = vec![];
+        let mut node = *node;
+
+        while let Some(index) = node {
+          let (parent, instruction) = nodes[index];
+          instructions.push(instruction);
+          node = parent;
+        }
+
+        instructions.reverse();
+        return Some(Script::new(instructions, mode));
+      }
+    }
+
+    if depth == max_len {
+      break;
+    }
+
+    let mut next_frontier = vec![];
+
+    for (state, node) in &frontier {
+      for &instruction in &candidates {
+        let offset = |register| match register {
+          Register::T => 0,
+          _ => words
+        };
+
+        let mut next_state = state.clone();
+
+        for w in 0..words {
+          let source = match instruction.source {
+            Register::Sensor(i) => sensor_values[i][w],
+            register => state[offset(register) + w]
+          };
+          let target = &mut next_state[offset(instruction.target) + w];
+
+          *target = match instruction.operation {
+            Operation::And => source & *target,
+            Operation::Or => source | *target,
+            Operation::Not => !source
+          };
+
+          if w == words - 1 {
+            *target &= padding;
+          }
+        }
+
+        if visited.contains(&next_state) {
+          continue;
+        }
+
+        if visited.len() >= limit {
+          return None;
+        }
+
+        let index = nodes.len();
+
+        nodes.push((*node, instruction));
+        visited.insert(next_state.clone());
+        next_frontier.push((next_state, Some(index)));
+      }
+    }
+
+    frontier = next_frontier;
+  }
+
+  None
+}
+
+#[test]
+fn parse_and_validate() {
+  let script = Script::parse("# jump over single holes\nNOT A J\n\nWALK\n").unwrap();
+  let not_a = Instruction { operation: Operation::Not, source: Register::Sensor(0), target: Register::J };
+
+  assert_eq!(script, Script::new(vec![not_a], Mode::Walk));
+  assert_eq!(Script::parse(&script.to_string()), Ok(script));
+
+  assert_eq!(Script::parse("NOT A J\nJUMP\nWALK"), Err(ScriptError::Syntax { line: 2, text: "JUMP".to_owned() }));
+  assert_eq!(Script::parse("NOT A\nWALK"), Err(ScriptError::Syntax { line: 1, text: "NOT A".to_owned() }));
+  assert_eq!(Script::parse("OR A X\nWALK"), Err(ScriptError::Syntax { line: 1, text: "OR A X".to_owned() }));
+  assert_eq!(Script::parse("WALK\nNOT A J"), Err(ScriptError::Syntax { line: 2, text: "NOT A J".to_owned() }));
+  assert_eq!(Script::parse("NOT A J"), Err(ScriptError::MissingMode));
+  assert_eq!(Script::parse(&format!("{}WALK", "NOT A J\n".repeat(16))), Err(ScriptError::TooLong(16)));
+
+  let write_sensor = Instruction { operation: Operation::Or, source: Register::T, target: Register::Sensor(1) };
+  assert_eq!(Script::parse("OR T B\nWALK"), Err(ScriptError::ReadOnlyTarget(write_sensor)));
+
+  let read_e = Instruction { operation: Operation::Not, source: Register::Sensor(4), target: Register::J };
+  assert_eq!(Script::parse("NOT E J\nWALK"), Err(ScriptError::SensorOutOfRange(read_e, Mode::Walk)));
+  assert!(Script::parse("NOT E J\nRUN").is_ok());
+}
+
+#[test]
+fn simulate_scripts() {
+  let hull = parse_hull("Didn't make it across:\n\n.................\n#####..#.########\n").unwrap();
+
+  assert_eq!(hull.len(), 17);
+  assert_eq!(Script::new(vec![], Mode::Walk).simulate(&hull), Err(5));
+  assert_eq!(Script::parse("NOT A J\nWALK").unwrap().simulate(&hull), Err(8));
+
+  // Jumps as soon as there is a hole within three tiles and ground to land on
+  let script = Script::parse("
+    NOT A J
+    NOT B T
+    OR T J
+    NOT C T
+    OR T J
+    AND D J
+    WALK
+  ").unwrap();
+
+  assert_eq!(script.simulate(&hull), Ok(()));
+}
+
+#[test]
+fn synthesize_scripts() {
+  let hulls = vec![parse_hull("#####..#.########").unwrap()];
+  let script = synthesize(&hulls, Mode::Walk, MAX_INSTRUCTIONS, 100_000).unwrap();
+
+  assert_eq!(script.validate(), Ok(()));
+  assert_eq!(script.simulate(&hulls[0]), Ok(()));
+  assert_eq!(synthesize(&hulls, Mode::Walk, MAX_INSTRUCTIONS, 1), None);
+  assert_eq!(synthesize(&hulls, Mode::Walk, 0, 100_000), None);
+}